@@ -0,0 +1,160 @@
+use crate::{nulls, secrets::SecretProvider, sensitive::Policy, Error, HclConverter};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+enum Source {
+    Text(String),
+    File(PathBuf),
+}
+
+/// Builds a [`HclConverter`] with its locals, variables and `meta` loaded.
+///
+/// ```no_run
+/// use ship::{HclConverter, Language};
+///
+/// let document = HclConverter::builder()
+///     .storage("/srv/configs")
+///     .file("app/index.hcl")
+///     .variable("replicas", 3)
+///     .build()?;
+///
+/// let json = document.render(&Language::JSON)?;
+/// # Ok::<(), ship::Error>(())
+/// ```
+pub struct Builder {
+    source: Option<Source>,
+    storage: Option<PathBuf>,
+    offline: bool,
+    policy: Policy,
+    nulls: Option<nulls::Policy>,
+    secrets: Option<Arc<dyn SecretProvider>>,
+    variables: hcl::Map<String, hcl::Value>,
+}
+
+impl Default for Builder {
+    fn default() -> Self { Self::new() }
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self {
+            source: None,
+            storage: None,
+            offline: false,
+            policy: Policy::Allow,
+            nulls: None,
+            secrets: None,
+            variables: hcl::Map::new(),
+        }
+    }
+
+    /// Renders the given HCL text.
+    pub fn source(mut self, text: impl Into<String>) -> Self {
+        self.source = Some(Source::Text(text.into()));
+        self
+    }
+
+    /// Renders a file, relative to the storage directory when one is set.
+    ///
+    /// Directories resolve to their `index.hcl`.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.source = Some(Source::File(path.into()));
+        self
+    }
+
+    /// Root directory that files are resolved in and may not escape.
+    pub fn storage(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.storage = Some(dir.into());
+        self
+    }
+
+    /// Overrides a `var` value, `const` values cannot be overridden.
+    pub fn variable(mut self, name: impl Into<String>, value: impl Into<hcl::Value>) -> Self {
+        self.variables.insert(name.into(), value.into());
+        self
+    }
+
+    /// Provider used by `secret::kv`, defaults to the one set with [`crate::secrets::set_default`].
+    pub fn secrets(mut self, provider: impl SecretProvider + 'static) -> Self {
+        self.secrets = Some(Arc::new(provider));
+        self
+    }
+
+    /// Makes network functions (`http::*`, `secret::kv`) fail instead of sending requests.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Whether sensitive values may be rendered, the stricter of this and `meta.sensitive` applies.
+    pub fn sensitive(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// How null values are rendered, overriding `meta.nulls`.
+    pub fn nulls(mut self, policy: nulls::Policy) -> Self {
+        self.nulls = Some(policy);
+        self
+    }
+
+    pub fn build<'c>(self) -> Result<HclConverter<'c>, Error> {
+        let mut hcl = match self.source {
+            Some(Source::Text(text)) => HclConverter::new(&text)?,
+            Some(Source::File(path)) => HclConverter::read(resolve(self.storage.as_deref(), &path)?)?,
+            None => HclConverter::new("")?,
+        };
+
+        if let Some(storage) = self.storage {
+            hcl.set_storage(storage);
+        }
+
+        hcl.secrets = self.secrets;
+        hcl.set_policy(self.policy);
+
+        if let Some(policy) = self.nulls {
+            hcl.set_nulls(policy);
+        }
+
+        if self.offline {
+            hcl.offline();
+        }
+
+        for (name, value) in self.variables {
+            hcl.set_variable(name, value);
+        }
+
+        hcl.prepare()?;
+        Ok(hcl)
+    }
+}
+
+pub(crate) fn resolve(storage: Option<&Path>, file: &Path) -> Result<PathBuf, Error> {
+    let mut path = match storage {
+        Some(storage) => storage.join(file),
+        None => file.to_owned(),
+    };
+
+    if path.is_dir() {
+        path = path.join("index.hcl");
+    }
+
+    let not_found = || Error::from(io::Error::new(io::ErrorKind::NotFound, format!("File '{}' not found", file.display())));
+
+    if let Some(storage) = storage {
+        let root = storage.canonicalize()?;
+        let canonical = path.canonicalize().map_err(|_| not_found())?;
+
+        if !canonical.starts_with(&root) {
+            return Err(Error::OutsideStorage(file.to_owned()));
+        }
+    }
+
+    match path.is_file() {
+        true => Ok(path),
+        false => Err(not_found()),
+    }
+}
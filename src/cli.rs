@@ -0,0 +1,50 @@
+pub mod check;
+pub mod functions;
+pub mod repl;
+pub mod watch;
+
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Path to the config file, defaults to config.hcl in the working directory
+    #[arg(long, global = true, env = "SHIP_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Start the configuration server (default)
+    Serve,
+    /// Parse and evaluate every HCL file in storage
+    Check {
+        /// Directory to check, defaults to the configured storage path
+        dir: Option<PathBuf>,
+        /// Skip files that call network functions (http::*, secret::kv)
+        #[arg(long)]
+        offline: bool,
+    },
+    /// List every built-in function with its signature
+    Functions {
+        /// Print the catalogue as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Evaluate HCL expressions interactively
+    Repl {
+        /// File whose locals, variables and meta are loaded into the context
+        file: Option<PathBuf>,
+    },
+    /// Render every HCL file into a directory and re-render on change
+    Watch {
+        /// Directory containing the HCL sources
+        src: PathBuf,
+        /// Directory the rendered files are written to
+        out: PathBuf,
+    },
+}
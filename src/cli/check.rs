@@ -0,0 +1,411 @@
+use crate::config;
+use ship::{functions::OFFLINE, nulls, Error, HclConverter, Language};
+
+use hcl::edit::{
+    parser,
+    structure::{Body, Structure},
+    Span,
+};
+
+use hcl::eval::{Context, ErrorKind, Evaluate};
+use owo_colors::OwoColorize;
+use std::{
+    collections::HashSet,
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    process,
+};
+
+enum Severity {
+    Error,
+    Warning,
+}
+
+struct Diagnostic {
+    line: Option<usize>,
+    severity: Severity,
+    message: String,
+}
+
+#[derive(Default)]
+struct Report {
+    skipped: bool,
+    /// The file has no `meta` block, which is only valid for files other files import or extend.
+    module: bool,
+    /// Canonical paths of the files imported or extended while checking.
+    references: Vec<PathBuf>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Report {
+    fn push(&mut self, severity: Severity, line: Option<usize>, message: impl ToString) {
+        self.diagnostics.push(Diagnostic {
+            line,
+            severity,
+            message: message.to_string(),
+        });
+    }
+
+    fn error(&mut self, line: Option<usize>, message: impl ToString) { self.push(Severity::Error, line, message) }
+
+    fn warning(&mut self, line: Option<usize>, message: impl ToString) { self.push(Severity::Warning, line, message) }
+
+    fn failed(&self) -> bool { self.diagnostics.iter().any(|d| matches!(d.severity, Severity::Error)) }
+}
+
+pub fn run(dir: Option<PathBuf>, offline: bool) {
+    let base = dir.unwrap_or_else(|| config::read().settings.storage.to_owned());
+
+    let mut files = Vec::new();
+    if let Err(err) = collect(&base, &mut files) {
+        eprintln!("{} cannot read {}: {err}", "error:".red(), base.display());
+        process::exit(1);
+    }
+
+    let reports: Vec<Report> = files.iter().map(|path| check(&base, path, offline)).collect();
+    let referenced: HashSet<PathBuf> = reports.iter().flat_map(|report| report.references.iter().cloned()).collect();
+
+    let (mut failed, mut skipped) = (0, 0);
+
+    for (path, mut report) in files.iter().zip(reports) {
+        let name = path.strip_prefix(&base).unwrap_or(path).display();
+
+        if report.module && !path.canonicalize().is_ok_and(|path| referenced.contains(&path)) {
+            report.error(None, "missing `meta` block, and no file imports or extends it");
+        }
+
+        for diagnostic in &report.diagnostics {
+            let location = match diagnostic.line {
+                Some(line) => format!("{name}:{line}"),
+                None => name.to_string(),
+            };
+
+            match diagnostic.severity {
+                Severity::Error => println!("{location}: {} {}", "error:".red(), diagnostic.message),
+                Severity::Warning => println!("{location}: {} {}", "warning:".yellow(), diagnostic.message),
+            }
+        }
+
+        if report.failed() {
+            failed += 1;
+        } else if report.skipped {
+            skipped += 1;
+            println!("{name}: {} uses network functions", "skipped:".dimmed());
+        }
+    }
+
+    let passed = files.len() - failed - skipped;
+    println!("\nchecked {} files: {passed} passed, {skipped} skipped, {failed} failed", files.len());
+
+    if failed > 0 {
+        process::exit(1);
+    }
+}
+
+pub(super) fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.map(|entry| entry.map(|e| e.path())).collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "hcl") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn check(base: &Path, path: &Path, offline: bool) -> Report {
+    let mut report = Report::default();
+
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            report.error(None, err);
+            return report;
+        }
+    };
+
+    let body = match parser::parse_body(&source) {
+        Ok(body) => body,
+        Err(err) => {
+            report.error(Some(err.location().line()), err.message());
+            return report;
+        }
+    };
+
+    const_conflicts(&body, &source, &mut report);
+
+    let mut hcl = match HclConverter::read(path) {
+        Ok(hcl) => hcl,
+        Err(err) => {
+            report.error(None, err);
+            return report;
+        }
+    };
+
+    hcl.set_storage(base);
+
+    if offline {
+        hcl.offline();
+    }
+
+    inspect(&mut hcl, &body, &source, &mut report);
+
+    report.references = hcl.imported();
+    report.references.extend(hcl.extends().iter().cloned());
+
+    // diagnostics can quote values that were marked sensitive while evaluating
+    for diagnostic in &mut report.diagnostics {
+        diagnostic.message = hcl.redact(&diagnostic.message);
+    }
+
+    report
+}
+
+/// Loads and evaluates the document, stopping at the first stage that fails.
+fn inspect(hcl: &mut HclConverter, body: &Body, source: &str, report: &mut Report) {
+    hcl.prelude();
+
+    if let Err(err) = hcl.fetch_functions() {
+        report.error(None, err);
+        return;
+    }
+
+    match hcl.fetch_imports().and_then(|_| hcl.fetch_locals()) {
+        Err(err) if uses_network(&err) => {
+            report.skipped = true;
+            return;
+        }
+        Err(err) => {
+            if !report.failed() {
+                report.error(None, err);
+            }
+            return;
+        }
+        Ok(()) => {}
+    }
+
+    match hcl.fetch_meta() {
+        Err(err) if uses_network(&err) => {
+            report.skipped = true;
+            return;
+        }
+        // files without `meta` are modules for `import` and `meta.extends`, they are evaluated but not rendered
+        Err(Error::MissingMeta) => report.module = true,
+        Err(err) => {
+            report.error(None, err);
+            return;
+        }
+        Ok(()) => {}
+    }
+
+    // function bodies reference their parameters and are only evaluated when called, variable blocks were checked
+    // above, and assertions and blocks generating others with `dynamic` are checked against the rendered value below
+    let structures = body.iter().filter(|structure| {
+        structure
+            .as_block()
+            .is_none_or(|block| !matches!(block.ident.as_str(), "function" | "variable" | "assert") && !generates(block))
+    });
+    hcl.scoped(|ctx| structures.for_each(|structure| evaluate(structure, source, ctx, report)));
+
+    if report.failed() || report.skipped || report.module {
+        return;
+    }
+
+    // every format and output is rendered from one evaluation, so network functions are called once
+    let evaluated = match hcl.evaluate() {
+        Ok(evaluated) => evaluated,
+        Err(err) => return failure(err, body, source, report),
+    };
+
+    if let Err(err) = evaluated.output() {
+        return failure(err, body, source, report);
+    }
+
+    let mut paths = Vec::new();
+    nulls(evaluated.value(), &mut Vec::new(), &mut paths);
+
+    for path in paths {
+        let line = line(source, locate(body, &path));
+        let path = path.join(".");
+
+        match hcl.nulls() {
+            nulls::Policy::Keep => report.warning(line, format!("`{path}` is null and will be rendered as the string \"null\" in toml")),
+            nulls::Policy::String => report.warning(line, format!("`{path}` is null and will be rendered as the string \"null\"")),
+            nulls::Policy::Error => report.error(line, format!("`{path}` is null, which the null policy does not allow")),
+            nulls::Policy::Omit => {}
+        }
+    }
+
+    if report.failed() {
+        return;
+    }
+
+    let mut languages = Language::all().to_vec();
+
+    // formats only the kind renders, such as systemd units
+    if !languages.contains(&hcl.language()) && hcl.language() != Language::None {
+        languages.push(hcl.language());
+    }
+
+    for lang in languages {
+        match evaluated.render(&lang) {
+            Err(Error::NumberOutput(pointers)) => {
+                for pointer in pointers {
+                    report.error(line(source, locate_pointer(body, &pointer)), format!("`{pointer}` does not fit into a {} integer", lang.extension()));
+                }
+            }
+            Err(err) => report.error(None, format!("cannot render {}: {err}", lang.extension())),
+            Ok(_) => {}
+        }
+    }
+
+    for block in body.get_blocks("output") {
+        let name = block.labels.first().map(|label| label.as_str()).unwrap_or_default();
+
+        if let Err(err) = evaluated.artifact(name) {
+            report.error(line(source, block.span()), format!("cannot render output `{name}`: {err}"));
+        }
+    }
+}
+
+/// Reports a failed evaluation, at the lines of the values or assertions it names.
+fn failure(err: Error, body: &Body, source: &str, report: &mut Report) {
+    match err {
+        Error::Schema(violations) => {
+            for violation in violations {
+                report.error(line(source, locate_pointer(body, &violation.pointer)), format!("schema: {violation}"));
+            }
+        }
+        Error::Compose(violations) => {
+            for violation in violations {
+                report.error(line(source, locate_pointer(body, &violation.pointer)), format!("compose: {violation}"));
+            }
+        }
+        Error::Assertions(failed) => {
+            for (name, message) in failed {
+                let block = body.get_blocks("assert").find(|block| block.labels.first().is_some_and(|label| label.as_str() == name));
+                report.error(line(source, block.and_then(|block| block.span())), format!("assertion `{name}` failed: {message}"));
+            }
+        }
+        Error::SensitiveOutput(pointers) => {
+            for pointer in pointers {
+                report.error(line(source, locate_pointer(body, &pointer)), format!("sensitive value in the output at {pointer}"));
+            }
+        }
+        err => report.error(None, err),
+    }
+}
+
+fn uses_network(err: &Error) -> bool {
+    match err {
+        Error::Hcl(hcl::Error::Eval(err)) => matches!(err.kind(), ErrorKind::FuncCall(_, msg) if msg == OFFLINE),
+        Error::Import { source, .. } => uses_network(source),
+        Error::InvalidSchema(msg) => msg == OFFLINE,
+        _ => false,
+    }
+}
+
+/// Whether the block is or contains a `dynamic` block, whose content references iterators only bound while it is expanded.
+fn generates(block: &hcl::edit::structure::Block) -> bool { block.ident.as_str() == "dynamic" || block.body.blocks().any(generates) }
+
+fn line(source: &str, span: Option<Range<usize>>) -> Option<usize> { span.map(|span| source[..span.start].matches('\n').count() + 1) }
+
+fn const_conflicts(body: &Body, source: &str, report: &mut Report) {
+    let consts: Vec<&str> = body.get_blocks("const").flat_map(|block| block.body.attributes()).map(|attr| attr.key.as_str()).collect();
+
+    for name in ["var", "let", "vars"] {
+        for attr in body.get_blocks(name).flat_map(|block| block.body.attributes()) {
+            if consts.contains(&attr.key.as_str()) {
+                report.error(line(source, attr.span()), format!("cannot override const `{}` in '{name}' block", attr.key.as_str()));
+            }
+        }
+    }
+}
+
+fn evaluate(structure: &Structure, source: &str, ctx: &Context, report: &mut Report) {
+    match structure {
+        // blocks dropped by their condition are not rendered, so their content may reference values that are not set
+        Structure::Block(block) if !holds(block, ctx) => {}
+        Structure::Block(block) => block.body.iter().for_each(|structure| evaluate(structure, source, ctx, report)),
+        Structure::Attribute(attr) => {
+            let expr = hcl::Expression::from(attr.value.clone());
+
+            if let Err(err) = expr.evaluate(ctx) {
+                let line = line(source, attr.span());
+
+                match err.kind() {
+                    ErrorKind::FuncCall(_, msg) if msg == OFFLINE => report.skipped = true,
+                    ErrorKind::UndefinedFunc(name) => report.error(line, format!("unknown function `{name}`")),
+                    _ => report.error(line, err),
+                }
+            }
+        }
+    }
+}
+
+/// Whether the `when` attribute of a block, or the `condition` of a `when {}` block, does not drop it from the output.
+/// Conditions that fail to evaluate count as holding, so they are reported with the rest of the block.
+fn holds(block: &hcl::edit::structure::Block, ctx: &Context) -> bool {
+    let key = match block.ident.as_str() == "when" && block.labels.is_empty() {
+        true => "condition",
+        false => "when",
+    };
+
+    let Some(condition) = block.body.attributes().find(|attr| attr.key.as_str() == key) else {
+        return true;
+    };
+    !matches!(hcl::Expression::from(condition.value.clone()).evaluate(ctx), Ok(hcl::Value::Bool(false)))
+}
+
+/// Collects the paths of the null values in `value`.
+fn nulls(value: &hcl::Value, path: &mut Vec<String>, out: &mut Vec<Vec<String>>) {
+    match value {
+        hcl::Value::Null => out.push(path.to_owned()),
+        hcl::Value::Array(arr) => {
+            for (index, item) in arr.iter().enumerate() {
+                path.push(index.to_string());
+                nulls(item, path, out);
+                path.pop();
+            }
+        }
+        hcl::Value::Object(obj) => {
+            for (key, item) in obj {
+                path.push(key.to_owned());
+                nulls(item, path, out);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Finds the definition of the value at a JSON pointer into the rendered document.
+fn locate_pointer(body: &Body, pointer: &str) -> Option<Range<usize>> {
+    let path: Vec<String> = pointer.split('/').skip(1).map(|segment| segment.replace("~1", "/").replace("~0", "~")).collect();
+    locate(body, &path)
+}
+
+fn locate(body: &Body, path: &[String]) -> Option<Range<usize>> {
+    let (key, rest) = path.split_first()?;
+
+    for structure in body.iter() {
+        match structure {
+            Structure::Attribute(attr) if attr.key.as_str() == key => return attr.span(),
+            Structure::Block(block) if block.ident.as_str() == key => {
+                let labels: Vec<&str> = block.labels.iter().map(|label| label.as_str()).collect();
+
+                if rest.len() >= labels.len() && rest.iter().zip(&labels).all(|(a, b)| a == b) {
+                    return locate(&block.body, &rest[labels.len()..]).or(block.span());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
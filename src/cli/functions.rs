@@ -0,0 +1,35 @@
+use owo_colors::OwoColorize;
+use ship::functions;
+
+pub fn run(json: bool) {
+    let catalog = functions::catalog();
+
+    if json {
+        return println!("{}", serde_json::to_string_pretty(&catalog).unwrap_or_default());
+    }
+
+    let mut namespace = None;
+
+    for signature in &catalog {
+        if namespace != Some(&signature.namespace) {
+            namespace = Some(&signature.namespace);
+            match &signature.namespace {
+                Some(ns) => println!("\n{}", format!("{ns}::").bold()),
+                None => println!("{}", "global".bold()),
+            }
+        }
+
+        println!("  {}", signature.cyan());
+        println!("      {}", signature.description);
+
+        if let Some(example) = signature.example {
+            println!("      {} {example}", "example:".dimmed());
+        }
+    }
+
+    let disabled: Vec<String> = functions::modules().into_iter().filter(|(_, enabled)| !enabled).map(|(name, _)| name).collect();
+
+    if !disabled.is_empty() {
+        println!("\n{} {}", "disabled modules:".dimmed(), disabled.join(", "));
+    }
+}
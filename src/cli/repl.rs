@@ -0,0 +1,159 @@
+use hcl::{edit::parser, eval::Evaluate};
+use owo_colors::OwoColorize;
+use ship::{functions, HclConverter};
+use std::{path::PathBuf, process};
+
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    history::DefaultHistory,
+    Context, Editor, Helper, Highlighter, Hinter, Validator,
+};
+
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct Names {
+    names: Vec<String>,
+}
+
+impl Names {
+    fn new(hcl: &HclConverter) -> Self {
+        let mut names: Vec<String> = functions::catalog().into_iter().map(|signature| format!("{}(", signature.name)).collect();
+        names.extend(hcl.functions().map(|name| format!("{name}(")));
+
+        for (name, value) in hcl.variables() {
+            names.push(name.to_owned());
+
+            if let hcl::Value::Object(obj) = value {
+                names.extend(obj.keys().map(|key| format!("{name}.{key}")));
+            }
+        }
+
+        names.sort();
+        names.dedup();
+
+        Self { names }
+    }
+}
+
+impl Completer for Names {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos].rfind(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.'))).map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+
+        let candidates = self
+            .names
+            .iter()
+            .filter(|name| name.starts_with(word))
+            .map(|name| Pair {
+                display: name.to_owned(),
+                replacement: name.to_owned(),
+            })
+            .collect();
+
+        Ok((start, candidates))
+    }
+}
+
+pub fn run(file: Option<PathBuf>) {
+    let hcl = match file {
+        Some(path) => load(path),
+        None => {
+            let mut hcl = HclConverter::new("").expect("empty source is always valid");
+            hcl.prelude();
+            hcl
+        }
+    };
+
+    let mut editor: Editor<Names, DefaultHistory> = match Editor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            eprintln!("{} {err}", "error:".red());
+            process::exit(1);
+        }
+    };
+
+    editor.set_helper(Some(Names::new(&hcl)));
+    println!("ship {} repl, type {} for help", env!("CARGO_PKG_VERSION"), ":help".bold());
+
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("{} {err}", "error:".red());
+                break;
+            }
+        };
+
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+
+        let _ = editor.add_history_entry(input);
+
+        match input {
+            ":q" | ":quit" | ":exit" => break,
+            ":help" => help(),
+            ":vars" => {
+                for (name, value) in hcl.variables() {
+                    println!("{} = {}", name.cyan(), hcl.redact(&pretty(value)));
+                }
+            }
+            _ => match evaluate(&hcl, input) {
+                Ok(value) => println!("{}", hcl.redact(&pretty(&value))),
+                Err(err) => println!("{} {}", "error:".red(), hcl.redact(&err)),
+            },
+        }
+    }
+}
+
+fn load(path: PathBuf) -> HclConverter<'static> {
+    let mut hcl = match HclConverter::read(&path) {
+        Ok(hcl) => hcl,
+        Err(err) => {
+            eprintln!("{} cannot read {}: {err}", "error:".red(), path.display());
+            process::exit(1);
+        }
+    };
+
+    hcl.prelude();
+
+    if let Err(err) = hcl.fetch_functions().and_then(|_| hcl.fetch_imports()) {
+        eprintln!("{} {}", "error:".red(), hcl.redact(&err.to_string()));
+        process::exit(1);
+    }
+
+    if let Err(err) = hcl.fetch_locals() {
+        eprintln!("{} {}", "error:".red(), hcl.redact(&err.to_string()));
+        process::exit(1);
+    }
+
+    if let Err(err) = hcl.fetch_meta() {
+        eprintln!("{} {}, continuing without meta", "warning:".yellow(), hcl.redact(&err.to_string()));
+    }
+
+    hcl
+}
+
+fn evaluate(hcl: &HclConverter, input: &str) -> Result<hcl::Value, String> {
+    let expr = parser::parse_expr(input).map_err(|err| err.to_string())?;
+    hcl.scoped(|ctx| hcl::Expression::from(expr).evaluate(ctx)).map_err(|err| err.to_string())
+}
+
+fn pretty(value: &hcl::Value) -> String {
+    match value {
+        hcl::Value::Array(_) | hcl::Value::Object(_) => hcl::format::to_string(value).unwrap_or_else(|_| value.to_string()),
+        _ => value.to_string(),
+    }
+}
+
+fn help() {
+    println!("Evaluate any HCL expression against the function library, e.g. {}", r#"cidr::subnets("10.0.0.0/16", 2)"#.cyan());
+    println!("Press tab to complete function and variable names.\n");
+    println!("  {}  list declared variables", ":vars".bold());
+    println!("  {}  show this help", ":help".bold());
+    println!("  {}  exit the repl", ":quit".bold());
+}
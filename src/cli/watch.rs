@@ -0,0 +1,160 @@
+use super::check::collect;
+use ship::{Artifact, Error, HclConverter};
+
+use notify::{RecursiveMode, Watcher};
+use owo_colors::OwoColorize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Component, Path, PathBuf},
+    process,
+    sync::mpsc,
+    time::Duration,
+};
+
+struct Outputs {
+    src: PathBuf,
+    out: PathBuf,
+    targets: HashMap<PathBuf, Vec<PathBuf>>,
+    dependencies: HashMap<PathBuf, Vec<PathBuf>>,
+}
+
+impl Outputs {
+    fn render(&mut self, path: &Path) {
+        let name = path.strip_prefix(&self.src).unwrap_or(path).to_owned();
+
+        let Build { artifacts, dependencies } = match build(&self.src, &name) {
+            Ok(Some(build)) => build,
+            // files without `meta` are modules, rendered through the files importing or extending them
+            Ok(None) => return self.remove(path),
+            Err(err) => return eprintln!("{}: {} {err}", name.display(), "error:".red()),
+        };
+
+        self.dependencies.insert(path.to_owned(), dependencies);
+
+        let mut targets = Vec::new();
+
+        for artifact in artifacts {
+            let target = self.out.join(name.parent().unwrap_or(Path::new(""))).join(&artifact.file);
+
+            if let Err(err) = target.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(&target, artifact.data)) {
+                return eprintln!("{}: {} cannot write {}: {err}", name.display(), "error:".red(), target.display());
+            }
+
+            println!("{} {} -> {}", "rendered".green(), name.display(), target.display());
+            targets.push(target);
+        }
+
+        // files the source no longer renders to, such as removed `output` blocks
+        for previous in self.targets.insert(path.to_owned(), targets.to_owned()).unwrap_or_default() {
+            if !targets.contains(&previous) {
+                let _ = fs::remove_file(previous);
+            }
+        }
+    }
+
+    /// Files importing or extending `path`, directly or not, which need to be rendered again when it changes.
+    fn dependents(&self, path: &Path) -> Vec<PathBuf> {
+        self.dependencies
+            .iter()
+            .filter(|(_, dependencies)| dependencies.iter().any(|dependency| dependency == path))
+            .map(|(file, _)| file.to_owned())
+            .collect()
+    }
+
+    fn remove(&mut self, path: &Path) {
+        self.dependencies.remove(path);
+
+        for target in self.targets.remove(path).unwrap_or_default() {
+            let _ = fs::remove_file(&target);
+            println!("{} {}", "removed".yellow(), target.display());
+        }
+    }
+}
+
+pub fn run(src: PathBuf, out: PathBuf) {
+    let mut outputs = Outputs {
+        src: src.canonicalize().unwrap_or(src),
+        out,
+        targets: HashMap::new(),
+        dependencies: HashMap::new(),
+    };
+
+    let mut files = Vec::new();
+    if let Err(err) = collect(&outputs.src, &mut files) {
+        eprintln!("{} cannot read {}: {err}", "error:".red(), outputs.src.display());
+        process::exit(1);
+    }
+
+    for path in &files {
+        outputs.render(path);
+    }
+
+    let (tx, rx) = mpsc::channel();
+
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            eprintln!("{} cannot start watcher: {err}", "error:".red());
+            process::exit(1);
+        }
+    };
+
+    if let Err(err) = watcher.watch(&outputs.src, RecursiveMode::Recursive) {
+        eprintln!("{} cannot watch {}: {err}", "error:".red(), outputs.src.display());
+        process::exit(1);
+    }
+
+    println!("watching {} for changes", outputs.src.display());
+
+    while let Ok(event) = rx.recv() {
+        let mut changed = BTreeSet::new();
+        let mut pending = Some(event);
+
+        // editors usually emit several events per save, collect them before rendering
+        while let Some(event) = pending {
+            match event {
+                Ok(event) if event.kind.is_access() => {}
+                Ok(event) => changed.extend(event.paths.into_iter().filter(|path| path.extension().is_some_and(|ext| ext == "hcl"))),
+                Err(err) => eprintln!("{} {err}", "error:".red()),
+            }
+            pending = rx.recv_timeout(Duration::from_millis(100)).ok();
+        }
+
+        let dependents: Vec<PathBuf> = changed.iter().flat_map(|path| outputs.dependents(path)).collect();
+        changed.extend(dependents);
+
+        for path in changed {
+            match path.is_file() {
+                true => outputs.render(&path),
+                false => outputs.remove(&path),
+            }
+        }
+    }
+}
+
+/// The files rendered from a source and the files it depends on.
+struct Build {
+    /// Rendered files, relative to the directory of the source.
+    artifacts: Vec<Artifact>,
+    dependencies: Vec<PathBuf>,
+}
+
+/// Renders `name`, or returns `None` for a module without a `meta` block.
+fn build(src: &Path, name: &Path) -> Result<Option<Build>, String> {
+    let hcl = match HclConverter::builder().storage(src).file(name).build() {
+        Ok(hcl) => hcl,
+        Err(Error::MissingMeta) => return Ok(None),
+        Err(err) => return Err(err.to_string()),
+    };
+    let artifacts = hcl.artifacts().map_err(|err| err.to_string())?;
+
+    if let Some(artifact) = artifacts.iter().find(|artifact| Path::new(&artifact.file).components().any(|c| !matches!(c, Component::Normal(_)))) {
+        return Err(format!("Output path '{}' escapes the output directory", artifact.file));
+    }
+
+    let mut dependencies = hcl.imported();
+    dependencies.extend(hcl.extends().iter().cloned());
+
+    Ok(Some(Build { artifacts, dependencies }))
+}
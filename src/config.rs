@@ -1,12 +1,93 @@
 use crate::models::Config;
 use macros_rs::fmt::{crashln, string};
 use owo_colors::OwoColorize;
-use std::fs;
+use ship::{
+    functions,
+    plugins::{Limits, Plugin},
+    secrets::SecretProvider,
+};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 
-pub(crate) fn read() -> Config {
-    let contents = match fs::read_to_string("config.hcl") {
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+pub(crate) fn init(path: Option<PathBuf>) {
+    if let Some(path) = path {
+        PATH.get_or_init(|| path);
+    }
+}
+
+pub(crate) fn read() -> &'static Config { CONFIG.get_or_init(load) }
+
+/// Registers the configured plugins and applies the `functions` settings when a config file is present.
+pub(crate) fn modules() {
+    if PATH.get().is_none() && fs::metadata("config.hcl").is_err() {
+        return;
+    }
+
+    let base = PATH.get().and_then(|path| path.parent()).unwrap_or(Path::new(""));
+
+    for (name, plugin) in &read().settings.plugin {
+        let defaults = Limits::default();
+        let limits = Limits {
+            fuel: plugin.fuel.unwrap_or(defaults.fuel),
+            memory: plugin.memory.map_or(defaults.memory, |mib| mib << 20),
+        };
+
+        match Plugin::load(name, base.join(&plugin.source), limits) {
+            Ok(plugin) => functions::register(plugin),
+            Err(err) => crashln!("Cannot load plugins.\n{}", string!(err).white()),
+        }
+    }
+
+    let settings = &read().settings.functions;
+
+    if let Err(err) = functions::enable(&settings.enable).and_then(|_| functions::disable(&settings.disable)) {
+        crashln!("Cannot apply function settings.\n{}", string!(err).white());
+    }
+}
+
+/// Resolves `secret::kv` through the vault settings, loading the config on first use.
+pub(crate) struct Secrets;
+
+impl SecretProvider for Secrets {
+    fn kv(&self, path: &str) -> Result<hcl::Value, String> {
+        match &read().settings.vault {
+            Some(vault) => vault.kv(path),
+            None => Err("Vault not configured in server settings".into()),
+        }
+    }
+}
+
+fn load() -> Config {
+    let mut value = match PATH.get() {
+        Some(path) => parse(path),
+        None if fs::metadata("config.hcl").is_ok() => parse(&PathBuf::from("config.hcl")),
+        None => hcl::Value::Object(hcl::Map::new()),
+    };
+
+    if let Some(root) = value.as_object_mut() {
+        let settings = root.entry("settings".into()).or_insert_with(|| hcl::Value::Object(hcl::Map::new()));
+
+        if let Some(settings) = settings.as_object_mut() {
+            overrides(settings);
+        }
+    }
+
+    match hcl::from_value(value).map_err(|err| string!(err)) {
+        Ok(parsed) => parsed,
+        Err(err) => crashln!("Cannot parse config.\n{}", err.white()),
+    }
+}
+
+fn parse(path: &PathBuf) -> hcl::Value {
+    let contents = match fs::read_to_string(path) {
         Ok(contents) => contents,
-        Err(err) => crashln!("Cannot find config.\n{}", string!(err).white()),
+        Err(err) => crashln!("Cannot find config {}.\n{}", path.display(), string!(err).white()),
     };
 
     match hcl::from_str(&contents).map_err(|err| string!(err)) {
@@ -14,3 +95,31 @@ pub(crate) fn read() -> Config {
         Err(err) => crashln!("Cannot parse config.\n{}", err.white()),
     }
 }
+
+fn overrides(settings: &mut hcl::Map<String, hcl::Value>) {
+    if let Ok(listen) = env::var("SHIP_LISTEN") {
+        settings.insert("listen".into(), listen.into());
+    }
+
+    if let Ok(storage) = env::var("SHIP_STORAGE") {
+        settings.insert("storage".into(), storage.into());
+    }
+
+    let url = env::var("SHIP_VAULT_URL").ok();
+    let token = env::var("SHIP_VAULT_TOKEN").ok();
+
+    if url.is_none() && token.is_none() {
+        return;
+    }
+
+    let vault = settings.entry("vault".into()).or_insert_with(|| hcl::Value::Object(hcl::Map::new()));
+
+    if let Some(vault) = vault.as_object_mut() {
+        if let Some(url) = url {
+            vault.insert("url".into(), url.into());
+        }
+        if let Some(token) = token {
+            vault.insert("token".into(), token.into());
+        }
+    }
+}
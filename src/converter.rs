@@ -0,0 +1,1029 @@
+mod asserts;
+mod blocks;
+mod conditions;
+mod dynamic;
+mod extends;
+mod imports;
+mod locals;
+mod outputs;
+mod user;
+mod variables;
+
+use crate::{
+    builder,
+    functions::{self, Functions},
+    kinds::{self, Kind},
+    nulls, numbers,
+    schema::Schema,
+    secrets::{self, SecretProvider},
+    sensitive::{Marks, Policy},
+    Builder, Error,
+};
+
+use std::{
+    cell::Ref,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use blocks::Shapes;
+use extends::Lists;
+use hcl::{
+    eval::{Context, Evaluate},
+    Block,
+};
+use imports::Imports;
+use locals::{Entry, References};
+use outputs::Output;
+use serde_json::Value as JsonValue;
+use serde_yaml_ng::Value as YamlValue;
+use toml::Value as TomlValue;
+use user::UserFunc;
+
+pub use outputs::Artifact;
+pub(crate) use user::exceeded;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    YAML,
+    JSON,
+    TOML,
+    /// Rendered by kinds that have their own format, such as systemd units.
+    INI,
+    None,
+}
+
+impl FromStr for Language {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "toml" => Language::TOML,
+            "json" => Language::JSON,
+            "yml" | "yaml" => Language::YAML,
+            "ini" => Language::INI,
+            _ => Language::None,
+        })
+    }
+}
+
+impl Language {
+    pub fn parse(s: &str) -> Language { Language::from_str(s).expect("expected valid enum item") }
+
+    pub fn all() -> [Language; 3] { [Language::JSON, Language::YAML, Language::TOML] }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Language::TOML => "toml",
+            Language::JSON => "json",
+            Language::YAML => "yml",
+            Language::INI => "ini",
+            Language::None => "",
+        }
+    }
+}
+
+/// An evaluated document, before its policies and `meta.kind` are applied.
+struct Document {
+    value: hcl::Value,
+    meta: hcl::Map<String, hcl::Value>,
+    outputs: Vec<Output>,
+}
+
+/// The body without function bodies, variable declarations and assertions, which are not part of the output.
+fn content(body: hcl::Body) -> hcl::Body {
+    body.into_iter()
+        .filter(|structure| !matches!(structure, hcl::Structure::Block(block) if matches!(block.identifier(), "function" | "variable" | "assert")))
+        .collect()
+}
+
+/// A document evaluated by [`HclConverter::evaluate`], rendering it does not evaluate it again.
+pub struct Evaluated<'a, 'c> {
+    hcl: &'a HclConverter<'c>,
+    document: Document,
+}
+
+pub struct HclConverter<'c> {
+    data: String,
+    file: Option<String>,
+    export: Option<String>,
+    path: Option<PathBuf>,
+    storage: Option<PathBuf>,
+    module: Functions<'c>,
+    /// The functions `function` bodies are evaluated with, built once the `function` blocks are read.
+    library: Functions<'static>,
+    pub(crate) secrets: Option<Arc<dyn SecretProvider>>,
+    overrides: hcl::Map<String, hcl::Value>,
+    variables: hcl::Map<String, hcl::Value>,
+    functions: Vec<UserFunc>,
+    imports: Imports,
+    template: Option<(hcl::Value, Lists)>,
+    schema: Option<Schema>,
+    blocks: Shapes,
+    kind: Option<Arc<dyn Kind>>,
+    sensitive: Marks,
+    policy: Policy,
+    nulls: Option<nulls::Policy>,
+    extends: Vec<PathBuf>,
+    /// Canonical paths of the documents extending this one, from the rendered file.
+    lineage: Vec<PathBuf>,
+    offline: bool,
+}
+
+impl<'c> HclConverter<'c> {
+    pub fn new(input: &str) -> Result<Self, Error> {
+        let module = functions::init();
+
+        let default = Self {
+            module,
+            library: Functions::default(),
+            file: None,
+            path: None,
+            export: None,
+            storage: None,
+            secrets: None,
+            overrides: hcl::Map::new(),
+            variables: hcl::Map::new(),
+            functions: Vec::new(),
+            imports: Imports::default(),
+            template: None,
+            schema: None,
+            blocks: Shapes::default(),
+            kind: None,
+            sensitive: Marks::default(),
+            policy: Policy::Allow,
+            nulls: None,
+            extends: Vec::new(),
+            lineage: Vec::new(),
+            offline: false,
+            data: input.to_owned(),
+        };
+
+        Ok(default)
+    }
+
+    pub fn read<F>(path: F) -> Result<Self, Error>
+    where
+        F: Into<PathBuf>,
+    {
+        let path = path.into();
+        let content = fs::read_to_string(&path)?;
+
+        let mut converter = Self::new(&content)?;
+        converter.path = Some(path);
+
+        Ok(converter)
+    }
+
+    /// Starts building a document, see [`Builder`].
+    pub fn builder() -> Builder { Builder::new() }
+
+    /// Output file name from `meta.file`, without its extension.
+    pub fn file_name(&self) -> Option<&str> { self.file.as_deref() }
+
+    /// Output format from the `meta.file` extension or `meta.export`.
+    pub fn export(&self) -> Option<&str> { self.export.as_deref() }
+
+    /// Format to render in when the request names none, from [`export`](Self::export) or else the default of the `meta.kind`.
+    pub fn language(&self) -> Language {
+        match Language::parse(self.export().unwrap_or_default()) {
+            Language::None => self.kind.as_ref().and_then(|kind| kind.format()).unwrap_or(Language::None),
+            lang => lang,
+        }
+    }
+
+    /// File extension for output in `lang`, keeping a `meta.file` extension only the kind knows, e.g. `web.service`.
+    pub fn extension(&self, lang: &Language) -> &str {
+        match self.export() {
+            Some(export) if Language::parse(export) == Language::None && self.kind.is_some() && self.language() == *lang => export,
+            _ => lang.extension(),
+        }
+    }
+
+    /// Path the source was read from, if any.
+    pub fn path(&self) -> Option<&Path> { self.path.as_deref() }
+
+    pub fn storage(&self) -> Option<&Path> { self.storage.as_deref() }
+
+    pub fn set_storage(&mut self, storage: impl Into<PathBuf>) { self.storage = Some(storage.into()); }
+
+    pub fn set_secrets(&mut self, provider: impl SecretProvider + 'static) { self.secrets = Some(Arc::new(provider)); }
+
+    /// Sets whether sensitive values may be rendered, the stricter of this and `meta.sensitive` applies.
+    pub fn set_policy(&mut self, policy: Policy) { self.policy = self.policy.max(policy); }
+
+    /// Sets how null values are rendered, overriding `meta.nulls`.
+    pub fn set_nulls(&mut self, policy: nulls::Policy) { self.nulls = Some(policy); }
+
+    /// How null values are rendered, from the request or `meta.nulls`.
+    pub fn nulls(&self) -> nulls::Policy { self.nulls.unwrap_or_default() }
+
+    /// Replaces the values marked sensitive so far in `text`.
+    pub fn redact(&self, text: &str) -> String { self.sensitive.redact(text) }
+
+    /// Overrides a `var` value, `const` values cannot be overridden.
+    pub fn set_variable<T: Into<hcl::Value>>(&mut self, name: impl Into<String>, value: T) { self.overrides.insert(name.into(), value.into()); }
+
+    pub fn declare<I, T>(&mut self, name: I, value: T)
+    where
+        I: Into<hcl::Identifier>,
+        T: Into<hcl::Value>,
+    {
+        let (name, value) = (name.into(), value.into());
+
+        self.variables.insert(name.to_string(), value.clone());
+        self.module.borrow_mut().declare_var(name, value);
+    }
+
+    pub fn variables(&self) -> &hcl::Map<String, hcl::Value> { &self.variables }
+
+    /// Names of the functions declared with `function` blocks.
+    pub fn functions(&self) -> impl Iterator<Item = &str> { self.functions.iter().map(UserFunc::name) }
+
+    /// Canonical paths of every file imported while preparing the document, directly or not.
+    pub fn imported(&self) -> Vec<PathBuf> { self.imports.cache.borrow().keys().cloned().collect() }
+
+    /// Canonical paths of the files named by `meta.extends`, nearest parent first.
+    pub fn extends(&self) -> &[PathBuf] { &self.extends }
+
+    /// The storage directory, or this file's directory without one, and `source` relative to it.
+    fn locate(&self, source: &str) -> (PathBuf, PathBuf) {
+        let dir = self.path.as_deref().and_then(Path::parent).map(Path::to_owned);
+        let root = self.storage.clone().or(dir.clone()).unwrap_or_default();
+        let dir = dir.unwrap_or(root.clone());
+
+        let file = dir.strip_prefix(&root).unwrap_or(&dir).join(source);
+        (root, file)
+    }
+
+    /// Evaluates the files named by `import` blocks and declares their output as `import`.
+    ///
+    /// Sources resolve relative to this file and may not leave the storage directory, or this
+    /// file's directory without one.
+    pub fn fetch_imports(&mut self) -> Result<(), Error> {
+        let imports = imports::collect(&hcl::parse(&self.data)?)?;
+
+        if imports.is_empty() {
+            return Ok(());
+        }
+
+        if self.imports.chain.is_empty() {
+            self.imports.chain = self.path.as_deref().map(Path::canonicalize).transpose()?.into_iter().collect();
+        }
+
+        let mut values = hcl::Map::new();
+
+        for (name, source) in imports {
+            let (root, file) = self.locate(&source);
+            let value = self.import(&root, &file).map_err(|err| match err {
+                Error::ImportCycle(_) => err,
+                err => Error::Import {
+                    name: name.to_owned(),
+                    source: Box::new(err),
+                },
+            })?;
+
+            values.insert(name, value);
+        }
+
+        self.declare("import", values);
+        Ok(())
+    }
+
+    fn import(&self, root: &Path, file: &Path) -> Result<hcl::Value, Error> {
+        let path = builder::resolve(Some(root), file)?.canonicalize()?;
+        let imports = self.imports.enter(&path, root)?;
+
+        if let Some(value) = imports.cached(&path) {
+            return Ok(value);
+        }
+
+        let mut module = HclConverter::read(&path)?;
+
+        module.storage = Some(root.to_owned());
+        module.secrets = self.secrets.clone();
+        module.imports = imports.clone();
+        module.sensitive = self.sensitive.clone();
+
+        if self.offline {
+            module.offline();
+        }
+
+        module.prelude();
+        module.fetch_functions()?;
+        module.fetch_imports()?;
+        module.fetch_locals()?;
+
+        match module.fetch_meta() {
+            Ok(()) | Err(Error::MissingMeta) => {}
+            Err(err) => return Err(err),
+        }
+
+        let value = module.value()?;
+        imports.store(path, value.to_owned());
+
+        Ok(value)
+    }
+
+    /// Renders the file named by `meta.extends` with the same overrides, returning its value and its own chain.
+    fn extend(&self, source: &str) -> Result<(hcl::Value, Vec<PathBuf>), Error> {
+        let (root, file) = self.locate(source);
+        let path = builder::resolve(Some(&root), &file)?.canonicalize()?;
+
+        let mut lineage = self.lineage.clone();
+
+        if lineage.is_empty() {
+            lineage.extend(self.path.as_deref().map(Path::canonicalize).transpose()?);
+        }
+
+        if let Some(start) = lineage.iter().position(|entry| *entry == path) {
+            let root = root.canonicalize()?;
+            let files = lineage[start..].iter().chain([&path]);
+
+            return Err(Error::ExtendsCycle(files.map(|entry| entry.strip_prefix(&root).unwrap_or(entry).display().to_string()).collect()));
+        }
+
+        lineage.push(path.to_owned());
+
+        let mut parent = HclConverter::read(&path)?;
+
+        parent.storage = Some(root);
+        parent.secrets = self.secrets.clone();
+        parent.overrides = self.overrides.clone();
+        parent.imports.cache = self.imports.cache.clone();
+        parent.sensitive = self.sensitive.clone();
+        parent.lineage = lineage;
+
+        if self.offline {
+            parent.offline();
+        }
+
+        parent.prelude();
+        parent.fetch_functions()?;
+        parent.fetch_imports()?;
+        parent.fetch_locals()?;
+
+        match parent.fetch_meta() {
+            Ok(()) | Err(Error::MissingMeta) => {}
+            Err(err) => return Err(err),
+        }
+
+        let mut chain = vec![path];
+        chain.extend(parent.extends.iter().cloned());
+
+        Ok((parent.value()?, chain))
+    }
+
+    /// Evaluates `locals` and the variable blocks in dependency order and declares them as `local` and `var`.
+    pub fn fetch_locals(&mut self) -> Result<(), Error> {
+        let body = hcl::edit::parser::parse_body(&self.data).map_err(hcl::Error::from)?;
+
+        let locals = locals::entries(&body, "locals");
+        let var = locals::entries(&body, "var");
+        let vars = locals::entries(&body, "vars");
+        let let_block = locals::entries(&body, "let");
+        let const_block = locals::entries(&body, "const");
+
+        let mut combined: hcl::Map<String, Entry> = hcl::Map::new();
+
+        if let Some(const_map) = &const_block {
+            combined.extend(const_map.iter().map(|(key, entry)| (key.to_owned(), Entry::Source(entry.to_owned()))));
+        }
+
+        let check_const_conflicts = |keys: Vec<&String>, block_name: &str| -> Result<(), Error> {
+            if let Some(const_map) = &const_block {
+                let conflicting_keys: Vec<String> = keys.into_iter().filter(|k| const_map.contains_key(*k)).map(|k| k.to_string()).collect();
+
+                if !conflicting_keys.is_empty() {
+                    return Err(Error::ConstOverride {
+                        block: block_name.to_string(),
+                        keys: conflicting_keys,
+                    });
+                }
+            }
+            Ok(())
+        };
+
+        if let Some(var_map) = var {
+            check_const_conflicts(var_map.keys().collect(), "var")?;
+            combined.extend(var_map.into_iter().map(|(key, entry)| (key, Entry::Source(entry))));
+        }
+
+        if let Some(let_map) = let_block {
+            check_const_conflicts(let_map.keys().collect(), "let")?;
+            combined.extend(let_map.into_iter().map(|(key, entry)| (key, Entry::Source(entry))));
+        }
+
+        if let Some(vars_map) = vars {
+            check_const_conflicts(vars_map.keys().collect(), "vars")?;
+
+            let conflicting_keys: Vec<String> = vars_map.keys().filter(|k| combined.contains_key(*k)).map(|k| k.to_string()).collect();
+
+            if !conflicting_keys.is_empty() {
+                return Err(Error::ConflictingVars(conflicting_keys));
+            }
+
+            combined.extend(vars_map.into_iter().map(|(key, entry)| (key, Entry::Source(entry))));
+        }
+
+        if !self.overrides.is_empty() {
+            check_const_conflicts(self.overrides.keys().collect(), "override")?;
+            combined.extend(self.overrides.iter().map(|(key, value)| (key.to_owned(), Entry::Value(value.to_owned()))));
+        }
+
+        // `variable` blocks take their value from the variable blocks or an override, then from `default`
+        let declarations = variables::collect(&body)?;
+        check_const_conflicts(declarations.iter().map(|variable| &variable.name).collect(), "variable")?;
+
+        for variable in &declarations {
+            if combined.contains_key(&variable.name) {
+                continue;
+            }
+
+            let default = variable.default.ok_or_else(|| Error::InvalidVariable {
+                name: variable.name.to_owned(),
+                message: "no value was given and it has no default".into(),
+            })?;
+
+            combined.insert(variable.name.to_owned(), Entry::Default(default));
+        }
+
+        let mut nodes: hcl::Map<String, Entry> = combined.into_iter().map(|(key, entry)| (format!("var.{key}"), entry)).collect();
+        nodes.extend(locals.iter().flatten().map(|(key, entry)| (format!("local.{key}"), Entry::Source(entry.to_owned()))));
+
+        let functions = locals::functions(&body);
+        let graph = nodes
+            .iter()
+            .map(|(key, entry)| match entry {
+                Entry::Source(structures) => (key.to_owned(), locals::dependencies(&References::of(structures).through(&functions), &nodes)),
+                Entry::Default(expr) => (key.to_owned(), locals::dependencies(&References::of_expression(expr).through(&functions), &nodes)),
+                Entry::Value(_) => (key.to_owned(), Vec::new()),
+            })
+            .collect();
+
+        let order = locals::order(&graph).map_err(Error::CyclicVariables)?;
+        let mut values: hcl::Map<String, hcl::Map<String, hcl::Value>> = hcl::Map::new();
+
+        for node in order {
+            let (namespace, key) = node.split_once('.').expect("node keys are namespaced");
+
+            let entry = &nodes[&node];
+
+            if !matches!(entry, Entry::Value(_)) {
+                for (namespace, entries) in &values {
+                    self.declare(namespace.as_str(), entries.to_owned());
+                }
+            }
+
+            let value = match entry {
+                Entry::Value(value) => value.to_owned(),
+                Entry::Source(structures) => self.evaluate_entry(key, structures)?,
+                Entry::Default(expr) => self.scoped(|ctx| hcl::Expression::from((*expr).to_owned()).evaluate(ctx)).map_err(hcl::Error::from)?,
+            };
+
+            let value = match declarations.iter().find(|variable| namespace == "var" && variable.name == key) {
+                Some(variable) => variable.check(value)?,
+                None => value,
+            };
+
+            values.entry(namespace.to_owned()).or_default().insert(key.to_owned(), value);
+        }
+
+        // declare in source order, with a `local` and `var` object for every block present
+        for namespace in locals::NAMESPACES {
+            let prefix = format!("{namespace}.");
+            let mut entries: hcl::Map<String, hcl::Value> = hcl::Map::new();
+
+            for key in nodes.keys().filter_map(|node| node.strip_prefix(&prefix)) {
+                entries.insert(key.to_owned(), values[namespace][key].to_owned());
+            }
+
+            if !entries.is_empty() || (namespace == "local" && locals.is_some()) {
+                self.declare(namespace, entries);
+            }
+        }
+
+        for variable in &declarations {
+            self.check_variable(variable)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_variable(&self, variable: &variables::Variable) -> Result<(), Error> {
+        for validation in &variable.validations {
+            match self.scoped(|ctx| validation.condition.evaluate(ctx)).map_err(hcl::Error::from)? {
+                hcl::Value::Bool(true) => {}
+                hcl::Value::Bool(false) => {
+                    let message = match self.scoped(|ctx| validation.message.evaluate(ctx)).map_err(hcl::Error::from)? {
+                        hcl::Value::String(message) => message,
+                        message => message.to_string(),
+                    };
+
+                    return Err(Error::InvalidVariable {
+                        name: variable.name.to_owned(),
+                        message,
+                    });
+                }
+                other => {
+                    return Err(Error::InvalidDeclaration(format!(
+                        "Variable '{}' has a validation condition that is not a bool: {other}",
+                        variable.name
+                    )))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn evaluate_entry(&self, key: &str, structures: &[&hcl::edit::structure::Structure]) -> Result<hcl::Value, Error> {
+        let body: hcl::Body = structures.iter().map(|structure| hcl::Structure::from((*structure).to_owned())).collect();
+        let body = self.scoped(|ctx| body.evaluate(ctx)).map_err(hcl::Error::from)?;
+        let mut value: hcl::Map<String, hcl::Value> = hcl::from_body(body)?;
+
+        Ok(value.shift_remove(key).unwrap_or(hcl::Value::Null))
+    }
+
+    /// Declares the `function` blocks as functions callable from the document.
+    pub fn fetch_functions(&mut self) -> Result<(), Error> {
+        self.functions = user::collect(&hcl::parse(&self.data)?)?;
+        user::declare(&mut self.module.borrow_mut(), &self.functions);
+
+        if self.functions.is_empty() {
+            return Ok(());
+        }
+
+        self.library = functions::init();
+
+        if self.offline {
+            functions::offline(self.library.borrow_mut());
+        }
+
+        user::declare(&mut self.library.borrow_mut(), &self.functions);
+        Ok(())
+    }
+
+    pub fn fetch_meta(&mut self) -> Result<(), Error> {
+        let value: hcl::Value = hcl::from_str(&self.data)?;
+        let obj = value.as_object().ok_or(Error::InvalidRoot)?;
+
+        let meta = obj.get("meta").and_then(|m| m.as_object()).ok_or(Error::MissingMeta)?;
+        let file = meta.get("file").and_then(|m| m.as_str()).map(|s| s.to_string());
+
+        self.kind = meta.get("kind").and_then(|k| k.as_str()).and_then(kinds::get);
+
+        if let Some(path) = file {
+            let (name, extension) = match path.rsplit_once('.') {
+                Some((name, ext)) => (name.to_string(), Some(ext.to_string())),
+                None => (path, meta.get("export").and_then(|m| m.as_str()).map(|s| s.to_string())),
+            };
+
+            self.file = Some(name);
+            self.export = extension;
+        }
+
+        if let Some(source) = meta.get("extends") {
+            let source = source.as_str().ok_or_else(|| Error::InvalidExtends("`meta.extends` needs to be a string".into()))?;
+            let lists = Lists::parse(meta.get("lists"))?;
+            let (parent, chain) = self.extend(source)?;
+
+            let root = self.storage.as_deref().map(Path::canonicalize).transpose()?.unwrap_or_default();
+            let files: Vec<_> = chain.iter().map(|path| path.strip_prefix(&root).unwrap_or(path).display().to_string()).collect();
+            tracing::debug!(file = ?self.path, extends = ?files, "resolved template chain");
+
+            self.template = Some((parent, lists));
+            self.extends = chain;
+        }
+
+        if let Some(source) = meta.get("schema") {
+            let source = source.as_str().ok_or_else(|| Error::InvalidSchema("`meta.schema` needs to be a string".into()))?;
+            self.schema = Some(self.load_schema(source)?);
+        }
+
+        self.blocks = Shapes::parse(meta.get("blocks"))?;
+
+        if let Some(policy) = meta.get("nulls").filter(|_| self.nulls.is_none()) {
+            let policy = policy.as_str().ok_or_else(|| Error::UnknownNullPolicy(policy.to_string()))?;
+            self.nulls = Some(policy.parse()?);
+        }
+
+        if let Some(policy) = meta.get("sensitive") {
+            let policy = policy.as_str().ok_or_else(|| Error::UnknownPolicy(policy.to_string()))?;
+            self.set_policy(policy.parse()?);
+        }
+
+        self.declare("meta", meta.to_owned());
+
+        if let Some(kind) = self.kind.clone() {
+            for (name, value) in kind.declare(&self.expanded()?) {
+                self.declare(name, value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The top-level values of the document with its `dynamic` blocks expanded, the blocks a false `when` drops
+    /// removed and `meta.blocks` applied, before its attributes are evaluated.
+    fn expanded(&self) -> Result<hcl::Map<String, hcl::Value>, Error> {
+        let body = content(hcl::parse(&self.data)?);
+        let body = self.scoped(|ctx| dynamic::expand(conditions::apply(body, ctx)?, ctx))?;
+
+        let hcl::Value::Object(obj) = hcl::from_body(self.blocks.apply(body)?)? else {
+            unreachable!("a body is an object")
+        };
+        Ok(obj)
+    }
+
+    /// Reads a schema from a URL, or from a path relative to the storage directory.
+    fn load_schema(&self, source: &str) -> Result<Schema, Error> {
+        let text = match source.starts_with("http://") || source.starts_with("https://") {
+            true if self.offline => return Err(Error::InvalidSchema(functions::OFFLINE.into())),
+            true => reqwest::blocking::get(source)
+                .and_then(|response| response.error_for_status())
+                .and_then(|response| response.text())
+                .map_err(|err| Error::InvalidSchema(format!("Cannot fetch schema '{source}': {err}")))?,
+            false => {
+                let (root, _) = self.locate(source);
+                fs::read_to_string(builder::resolve(Some(&root), Path::new(source))?)?
+            }
+        };
+
+        Schema::parse(&text).map_err(|err| Error::InvalidSchema(format!("Schema '{source}' {err}")))
+    }
+
+    pub fn prepare(&mut self) -> Result<(), Error> {
+        self.prelude();
+
+        let prepared = self
+            .fetch_functions()
+            .and_then(|_| self.fetch_imports())
+            .and_then(|_| self.fetch_locals())
+            .and_then(|_| self.fetch_meta());
+        prepared.map_err(|err| self.sensitive.redact_error(err))
+    }
+
+    pub fn prelude(&mut self) {
+        let version = Block::builder("version").add_attribute(("syntax", "v1")).add_attribute(("pkg", env!("CARGO_PKG_VERSION"))).build();
+
+        self.declare("boolean", true);
+        self.declare("number", 0);
+        self.declare("string", "");
+        self.declare("null", hcl::Value::Null);
+        self.declare("object", hcl::Map::new());
+        self.declare::<&str, Vec<String>>("array", vec![]);
+
+        self.declare("engine", version);
+    }
+
+    pub fn offline(&mut self) {
+        self.offline = true;
+        functions::offline(self.module.borrow_mut());
+        functions::offline(self.library.borrow_mut());
+    }
+
+    pub fn render(&self, lang: &Language) -> Result<String, Error> { self.evaluate()?.render(lang) }
+
+    pub fn context(&self) -> Ref<'_, Context<'c>> { self.module.borrow() }
+
+    /// Runs `f` with the document context while its secret provider and `function` blocks are in scope.
+    pub fn scoped<R>(&self, f: impl FnOnce(&Context<'c>) -> R) -> R {
+        let f = || user::scoped(&self.functions, || self.base(), || f(&self.module.borrow()));
+        self.sensitive.scoped(|| secrets::scoped(self.secrets.as_ref(), f))
+    }
+
+    /// A copy of the context that `function` bodies are evaluated in.
+    fn base(&self) -> Context<'static> {
+        let mut ctx = self.library.borrow().clone();
+
+        for (name, value) in &self.variables {
+            ctx.declare_var(name.as_str(), value.to_owned());
+        }
+
+        ctx
+    }
+
+    /// Checks `value` against the schema from `meta.schema`, if the document has one.
+    pub fn validate(&self, value: &hcl::Value) -> Result<(), Error> {
+        let Some(schema) = &self.schema else { return Ok(()) };
+
+        match schema.validate(&self.to_json(value)).as_slice() {
+            [] => Ok(()),
+            violations => Err(Error::Schema(violations.to_vec())),
+        }
+    }
+
+    /// Evaluates the document once, so it can be rendered in several formats and to its outputs without
+    /// calling functions such as `http::get` again.
+    pub fn evaluate(&self) -> Result<Evaluated<'_, 'c>, Error> {
+        let document = self.compute().map_err(|err| self.sensitive.redact_error(err))?;
+        Ok(Evaluated { hcl: self, document })
+    }
+
+    /// The value of the document once it passed validation, with the sensitive value policy applied and
+    /// turned into the shape its `meta.kind` renders as.
+    pub fn output(&self) -> Result<hcl::Value, Error> { self.evaluate()?.output() }
+
+    /// Applies the null policy to `value` for `lang`, logging the nulls that are rendered as strings.
+    fn settle(&self, value: hcl::Value, lang: &Language) -> Result<hcl::Value, Error> {
+        let (value, lossy) = self.nulls().apply(value, lang)?;
+
+        if !lossy.is_empty() {
+            tracing::warn!(file = ?self.path, format = lang.extension(), nulls = ?lossy, "null values rendered as strings");
+        }
+
+        Ok(value)
+    }
+
+    /// Serializes a rendered value as one TOML, YAML or JSON document.
+    fn encode(&self, value: &hcl::Value, lang: &Language) -> Result<String, Error> {
+        match lang {
+            Language::TOML => {
+                let mut overflows = Vec::new();
+                let value = self.to_toml(value, &mut String::new(), &mut overflows);
+
+                match overflows.is_empty() {
+                    true => Ok(toml::to_string_pretty(&value)?),
+                    false => Err(Error::NumberOutput(overflows)),
+                }
+            }
+            Language::YAML => Ok(serde_yaml_ng::to_string(&self.to_yaml(value))?),
+            Language::JSON => Ok(serde_json::to_string_pretty(&self.to_json(value))?),
+            Language::INI | Language::None => Err(Error::UnknownLanguage),
+        }
+    }
+
+    pub fn toml(&self) -> Result<String, Error> { self.render(&Language::TOML) }
+
+    pub fn yaml(&self) -> Result<String, Error> { self.render(&Language::YAML) }
+
+    pub fn json(&self) -> Result<String, Error> { self.render(&Language::JSON) }
+
+    /// Every file the document renders to: the document itself in its [`language`](Self::language), unless
+    /// it is empty and only declares `output` blocks, followed by one file per `output` block.
+    pub fn artifacts(&self) -> Result<Vec<Artifact>, Error> { self.evaluate()?.artifacts() }
+
+    /// The file rendered from the `output` block called `name`.
+    pub fn artifact(&self, name: &str) -> Result<Artifact, Error> { self.evaluate()?.artifact(name) }
+
+    fn eval(&self, body: hcl::Body) -> Result<(hcl::Value, Vec<Output>), Error> {
+        let body = content(body);
+        let body = self.scoped(|ctx| dynamic::expand(conditions::apply(body, ctx)?, ctx)?.evaluate(ctx).map_err(|err| Error::from(hcl::Error::from(err))))?;
+        let (body, outputs) = outputs::split(self.blocks.apply(body)?)?;
+
+        Ok((hcl::from_body(body)?, outputs))
+    }
+
+    /// Evaluates the document and strips the `locals`, `meta`, `function`, `import`, `assert` and variable
+    /// blocks, then merges it over the value of the file it extends and checks the `assert` blocks.
+    pub fn value(&self) -> Result<hcl::Value, Error> { self.compute().map(|document| document.value).map_err(|err| self.sensitive.redact_error(err)) }
+
+    /// The value of the document, its evaluated `meta` block and its `output` blocks.
+    fn compute(&self) -> Result<Document, Error> {
+        let body = hcl::parse(&self.data)?;
+        let assertions = asserts::collect(&body)?;
+        let (mut value, outputs) = self.eval(body)?;
+        let mut meta = hcl::Map::new();
+
+        if let hcl::Value::Object(obj) = &mut value {
+            obj.shift_remove("locals");
+            if let Some(hcl::Value::Object(evaluated)) = obj.shift_remove("meta") {
+                meta = evaluated;
+            }
+            obj.shift_remove("import");
+            obj.shift_remove("const");
+            obj.shift_remove("let");
+            obj.shift_remove("var");
+            obj.shift_remove("vars");
+        }
+
+        if let Some((parent, lists)) = &self.template {
+            value = lists.merge(parent.to_owned(), value);
+        }
+
+        if !assertions.is_empty() {
+            // assertions see the finished document as `self`
+            let failed = self.scoped(|ctx| {
+                let mut ctx = ctx.clone();
+                ctx.declare_var("self", value.to_owned());
+                asserts::check(&assertions, &ctx)
+            })?;
+
+            if !failed.is_empty() {
+                return Err(Error::Assertions(failed));
+            }
+        }
+
+        Ok(Document { value, meta, outputs })
+    }
+
+    /// Collects the JSON pointers of the integers TOML cannot represent into `overflows`.
+    fn to_toml(&self, hcl: &hcl::Value, pointer: &mut String, overflows: &mut Vec<String>) -> TomlValue {
+        match hcl {
+            hcl::Value::Null => TomlValue::String("null".to_string()), // TOML doesn't have a native null
+            hcl::Value::String(s) => TomlValue::String(s.clone()),
+            hcl::Value::Number(n) => numbers::to_toml(n).unwrap_or_else(|| {
+                overflows.push(pointer.to_owned());
+                TomlValue::Integer(0)
+            }),
+            hcl::Value::Bool(b) => TomlValue::Boolean(*b),
+            hcl::Value::Array(arr) => TomlValue::Array(arr.iter().enumerate().map(|(i, v)| self.to_toml_at(&i.to_string(), v, pointer, overflows)).collect()),
+            hcl::Value::Object(obj) => {
+                let mut map = toml::map::Map::new();
+                for (k, v) in obj {
+                    map.insert(k.clone(), self.to_toml_at(k, v, pointer, overflows));
+                }
+                TomlValue::Table(map)
+            }
+        }
+    }
+
+    fn to_toml_at(&self, segment: &str, hcl: &hcl::Value, pointer: &mut String, overflows: &mut Vec<String>) -> TomlValue {
+        let len = pointer.len();
+
+        pointer.push('/');
+        pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+        let value = self.to_toml(hcl, pointer, overflows);
+        pointer.truncate(len);
+
+        value
+    }
+
+    fn to_yaml(&self, hcl: &hcl::Value) -> YamlValue {
+        match hcl {
+            hcl::Value::Null => YamlValue::Null,
+            hcl::Value::String(s) => YamlValue::String(s.clone()),
+            hcl::Value::Number(n) => YamlValue::Number(numbers::to_yaml(n)),
+            hcl::Value::Bool(b) => YamlValue::Bool(*b),
+            hcl::Value::Array(arr) => YamlValue::Sequence(arr.iter().map(|v| self.to_yaml(v)).collect()),
+            hcl::Value::Object(obj) => {
+                let mut map = serde_yaml_ng::Mapping::new();
+                for (k, v) in obj {
+                    map.insert(YamlValue::String(k.clone()), self.to_yaml(v));
+                }
+                YamlValue::Mapping(map)
+            }
+        }
+    }
+
+    fn to_json(&self, hcl: &hcl::Value) -> JsonValue {
+        match hcl {
+            hcl::Value::Null => JsonValue::Null,
+            hcl::Value::String(s) => JsonValue::String(s.clone()),
+            hcl::Value::Number(n) => JsonValue::Number(numbers::to_json(n)),
+            hcl::Value::Bool(b) => JsonValue::Bool(*b),
+            hcl::Value::Array(arr) => JsonValue::Array(arr.iter().map(|v| self.to_json(v)).collect()),
+            hcl::Value::Object(obj) => {
+                let mut map = serde_json::Map::new();
+                for (k, v) in obj {
+                    map.insert(k.clone(), self.to_json(v));
+                }
+                JsonValue::Object(map)
+            }
+        }
+    }
+}
+
+impl Evaluated<'_, '_> {
+    /// The value of the document, see [`HclConverter::value`].
+    pub fn value(&self) -> &hcl::Value { &self.document.value }
+
+    /// The value once it passed validation, see [`HclConverter::output`].
+    pub fn output(&self) -> Result<hcl::Value, Error> {
+        let hcl = self.hcl;
+        let value = self.document.value.to_owned();
+
+        hcl.validate(&value).map_err(|err| hcl.sensitive.redact_error(err))?;
+
+        let Some(kind) = &hcl.kind else { return hcl.sensitive.apply(hcl.policy, value) };
+
+        kind.validate(&value).map_err(|err| hcl.sensitive.redact_error(err))?;
+        let value = hcl.sensitive.apply(hcl.policy, value)?;
+        kind.transform(value, &self.document.meta).map_err(|err| hcl.sensitive.redact_error(err))
+    }
+
+    /// The output rendered in `lang`, with the null policy applied.
+    pub fn render(&self, lang: &Language) -> Result<String, Error> {
+        let hcl = self.hcl;
+        let value = hcl.settle(self.output()?, lang)?;
+
+        match lang {
+            Language::INI => hcl.kind.as_ref().and_then(|kind| kind.render(&value, lang)).unwrap_or(Err(Error::UnknownLanguage)),
+            Language::YAML => match hcl.kind.as_ref().and_then(|kind| kind.stream(&value)) {
+                Some(documents) => Ok(documents.iter().map(|item| hcl.encode(item, lang)).collect::<Result<Vec<_>, _>>()?.join("---\n")),
+                None => hcl.encode(&value, lang),
+            },
+            Language::TOML | Language::JSON => hcl.encode(&value, lang),
+            Language::None => Err(Error::UnknownLanguage),
+        }
+    }
+
+    /// Every file the document renders to, see [`HclConverter::artifacts`].
+    pub fn artifacts(&self) -> Result<Vec<Artifact>, Error> {
+        let hcl = self.hcl;
+        let Document { value, outputs, .. } = &self.document;
+        let mut artifacts = Vec::new();
+
+        if outputs.is_empty() || value.as_object().is_none_or(|obj| !obj.is_empty()) {
+            let lang = hcl.language();
+            let stem = hcl
+                .file_name()
+                .map(str::to_owned)
+                .or_else(|| hcl.path().and_then(Path::file_stem).map(|stem| stem.to_string_lossy().into_owned()));
+
+            artifacts.push(Artifact {
+                name: None,
+                file: format!("{}.{}", stem.unwrap_or_default(), hcl.extension(&lang)),
+                data: self.render(&lang)?,
+            });
+        }
+
+        for output in outputs {
+            artifacts.push(self.artifact_of(output)?);
+        }
+
+        Ok(artifacts)
+    }
+
+    /// The file rendered from the `output` block called `name`.
+    pub fn artifact(&self, name: &str) -> Result<Artifact, Error> {
+        let output = self.document.outputs.iter().find(|output| output.name == name).ok_or_else(|| Error::UnknownOutput(name.to_owned()))?;
+        self.artifact_of(output)
+    }
+
+    /// Renders an `output` block in the format its file extension names, or else in the [`language`](HclConverter::language)
+    /// of the document, adding its extension when the file has none. The sensitive value and null policies apply, the schema and `meta.kind` do not.
+    fn artifact_of(&self, output: &Output) -> Result<Artifact, Error> {
+        let hcl = self.hcl;
+        let lang = output.language().unwrap_or_else(|| hcl.language());
+        let file = match Path::new(&output.file).extension() {
+            Some(_) => output.file.to_owned(),
+            None => format!("{}.{}", output.file, lang.extension()),
+        };
+
+        let value = hcl.settle(hcl.sensitive.apply(hcl.policy, output.value.to_owned())?, &lang)?;
+
+        Ok(Artifact {
+            data: hcl.encode(&value, &lang)?,
+            name: Some(output.name.to_owned()),
+            file,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        declare_fns,
+        functions::{self, Module, Signature},
+    };
+    use hcl::eval::FuncArgs;
+    use std::{
+        cell::RefMut,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counter;
+
+    fn tick(_args: FuncArgs) -> Result<hcl::Value, String> { Ok(CALLS.fetch_add(1, Ordering::SeqCst).into()) }
+
+    impl Module for Counter {
+        fn name(&self) -> &str { "counter" }
+
+        fn declare(&self, mut ctx: RefMut<Context>) -> Vec<Signature> {
+            declare_fns!(ctx, {
+                /// Counts its calls
+                tick => counter::tick()
+            })
+        }
+    }
+
+    #[test]
+    fn artifacts_evaluate_once() {
+        functions::register(Counter);
+
+        let source = "meta {\n  file = \"app.json\"\n}\n\ncalls = counter::tick()\n\noutput \"copy\" {\n  file  = \"copy.yml\"\n  value = { calls = counter::tick() }\n}\n";
+        let hcl = HclConverter::builder().source(source).build().unwrap();
+
+        let before = CALLS.load(Ordering::SeqCst);
+        let artifacts = hcl.artifacts().unwrap();
+
+        assert_eq!(artifacts.len(), 2);
+        assert_eq!(CALLS.load(Ordering::SeqCst) - before, 2);
+    }
+
+    #[test]
+    fn function_bodies_see_variables() {
+        let source = "meta {}\n\nlocals {\n  base = 10\n}\n\nfunction \"add\" {\n  params = [n]\n  result = local.base + n\n}\n\nsum = add(5)\n";
+        let hcl = HclConverter::builder().source(source).build().unwrap();
+
+        assert_eq!(hcl.value().unwrap(), hcl::Value::from_iter([("sum", hcl::Value::from(15))]));
+    }
+}
@@ -0,0 +1,66 @@
+use crate::Error;
+
+use hcl::{
+    eval::{Context, Evaluate},
+    structure::Body,
+    Expression,
+};
+
+/// An `assert "name" { condition = <expr> message = <expr> }` block.
+pub(crate) struct Assertion {
+    name: String,
+    condition: Expression,
+    message: Option<Expression>,
+}
+
+/// Reads the top-level `assert` blocks of `body`.
+pub(crate) fn collect(body: &Body) -> Result<Vec<Assertion>, Error> {
+    let mut assertions: Vec<Assertion> = Vec::new();
+
+    for block in body.blocks().filter(|block| block.identifier() == "assert") {
+        let name = match block.labels() {
+            [label] => label.as_str().to_owned(),
+            _ => return Err(Error::InvalidAssertion("Assert blocks need exactly one label".into())),
+        };
+
+        if assertions.iter().any(|assertion| assertion.name == name) {
+            return Err(Error::InvalidAssertion(format!("Assertion '{name}' is declared more than once")));
+        }
+
+        let attribute = |key: &str| block.body().attributes().find(|attr| attr.key() == key).map(|attr| attr.expr().to_owned());
+        let condition = attribute("condition").ok_or_else(|| Error::InvalidAssertion(format!("Assertion '{name}' has no `condition`")))?;
+
+        assertions.push(Assertion {
+            message: attribute("message"),
+            condition,
+            name,
+        });
+    }
+
+    Ok(assertions)
+}
+
+/// Evaluates every assertion against `ctx`, returning the name and message of those that fail.
+pub(crate) fn check(assertions: &[Assertion], ctx: &Context) -> Result<Vec<(String, String)>, Error> {
+    let mut failed = Vec::new();
+
+    for assertion in assertions {
+        let message = match assertion.condition.evaluate(ctx).map_err(hcl::Error::from)? {
+            hcl::Value::Bool(true) => continue,
+            hcl::Value::Bool(false) => match &assertion.message {
+                Some(message) => message.evaluate(ctx).map_err(hcl::Error::from)?,
+                None => hcl::Value::from("condition is false"),
+            },
+            other => return Err(Error::InvalidAssertion(format!("Assertion '{}' has a condition that is not a bool: {other}", assertion.name))),
+        };
+
+        let message = match message {
+            hcl::Value::String(message) => message,
+            message => message.to_string(),
+        };
+
+        failed.push((assertion.name.to_owned(), message));
+    }
+
+    Ok(failed)
+}
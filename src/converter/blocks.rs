@@ -0,0 +1,193 @@
+use crate::Error;
+
+use hcl::{
+    structure::{Attribute, Block, Body, Structure},
+    Expression,
+};
+
+/// What the blocks of one type are rendered as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Layout {
+    /// A list of objects, in the order the blocks are declared.
+    List,
+    /// An object keyed by the label of every block.
+    Map,
+}
+
+/// The `meta.blocks` entry of one block type.
+#[derive(Clone, Debug)]
+struct Shape {
+    layout: Layout,
+    /// Keys the labels of a block are added to its object under, in label order.
+    labels: Vec<String>,
+    /// Key the blocks are rendered under instead of their type.
+    rename: Option<String>,
+}
+
+/// The shapes from `meta.blocks` by block type.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Shapes(Vec<(String, Shape)>);
+
+impl Shapes {
+    /// Reads `meta.blocks`, which maps block types to `"list"`, `"map"` or `{ as, label, rename }`.
+    pub(crate) fn parse(value: Option<&hcl::Value>) -> Result<Self, Error> {
+        let entries = match value {
+            None => return Ok(Self::default()),
+            Some(hcl::Value::Object(entries)) => entries,
+            Some(_) => return Err(Error::InvalidBlocks("`meta.blocks` needs to be an object of block types".into())),
+        };
+
+        let mut shapes = Vec::new();
+
+        for (ident, entry) in entries {
+            let invalid = |message: &str| Error::InvalidBlocks(format!("`meta.blocks.{ident}` {message}"));
+
+            let (layout, options) = match entry {
+                hcl::Value::String(layout) => (layout.as_str(), &hcl::Map::new()),
+                hcl::Value::Object(options) => (options.get("as").and_then(hcl::Value::as_str).ok_or_else(|| invalid("needs `as` to be \"list\" or \"map\""))?, options),
+                _ => return Err(invalid("needs to be \"list\", \"map\" or an object")),
+            };
+
+            let layout = match layout {
+                "list" => Layout::List,
+                "map" => Layout::Map,
+                other => return Err(invalid(&format!("has an unknown shape \"{other}\", expected \"list\" or \"map\""))),
+            };
+
+            let labels = match options.get("label") {
+                // list items need their label somewhere, map keys already are the label
+                None if layout == Layout::List => vec!["name".to_owned()],
+                None => Vec::new(),
+                Some(hcl::Value::String(name)) => vec![name.to_owned()],
+                Some(hcl::Value::Array(names)) => names
+                    .iter()
+                    .map(|name| name.as_str().map(str::to_owned))
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| invalid("needs `label` to be a list of strings"))?,
+                Some(_) => return Err(invalid("needs `label` to be a string or a list of strings")),
+            };
+
+            let rename = match options.get("rename") {
+                None => None,
+                Some(hcl::Value::String(rename)) => Some(rename.to_owned()),
+                Some(_) => return Err(invalid("needs `rename` to be a string")),
+            };
+
+            if let Some(key) = options.keys().find(|key| !matches!(key.as_str(), "as" | "label" | "rename")) {
+                return Err(invalid(&format!("has an unknown option `{key}`")));
+            }
+
+            shapes.push((ident.to_owned(), Shape { layout, labels, rename }));
+        }
+
+        Ok(Self(shapes))
+    }
+
+    fn get(&self, ident: &str) -> Option<&Shape> { self.0.iter().find(|(name, _)| name == ident).map(|(_, shape)| shape) }
+
+    /// Replaces the blocks of an evaluated body, at any depth, whose type has a shape with one attribute holding
+    /// all of them, where the first of them was declared.
+    pub(crate) fn apply(&self, body: Body) -> Result<Body, Error> {
+        if self.0.is_empty() {
+            return Ok(body);
+        }
+
+        let mut structures = Vec::new();
+        // the index in `structures` the attribute goes to, the block type and its blocks
+        let mut groups: Vec<(usize, String, Vec<Block>)> = Vec::new();
+
+        for structure in body {
+            let mut block = match structure {
+                Structure::Block(block) => block,
+                attribute => {
+                    structures.push(attribute);
+                    continue;
+                }
+            };
+
+            block.body = self.apply(block.body)?;
+
+            if self.get(block.identifier()).is_none() {
+                structures.push(Structure::Block(block));
+                continue;
+            }
+
+            match groups.iter_mut().find(|(_, ident, _)| ident == block.identifier()) {
+                Some((_, _, blocks)) => blocks.push(block),
+                None => groups.push((structures.len(), block.identifier().to_owned(), vec![block])),
+            }
+        }
+
+        // later groups first, so the indices of the earlier ones stay valid
+        for (index, ident, blocks) in groups.into_iter().rev() {
+            let shape = self.get(&ident).expect("grouped blocks have a shape");
+            let key = shape.rename.as_deref().unwrap_or(&ident);
+
+            if structures.iter().any(|structure| matches!(structure, Structure::Attribute(attr) if attr.key() == key)) {
+                return Err(Error::InvalidBlocks(format!("`{ident}` blocks are rendered as `{key}`, which is also an attribute")));
+            }
+
+            let value = shape.value(&ident, blocks)?;
+            structures.insert(index, Structure::Attribute(Attribute::new(key, Expression::from(value))));
+        }
+
+        Ok(Body(structures))
+    }
+}
+
+impl Shape {
+    fn value(&self, ident: &str, blocks: Vec<Block>) -> Result<hcl::Value, Error> {
+        match self.layout {
+            Layout::List => Ok(hcl::Value::Array(blocks.into_iter().map(|block| self.object(ident, block)).collect::<Result<_, _>>()?)),
+            Layout::Map => {
+                let mut map = hcl::Map::new();
+
+                for block in blocks {
+                    let key = match block.labels() {
+                        [label] => label.as_str().to_owned(),
+                        _ => return Err(Error::InvalidBlocks(format!("`{ident}` blocks are rendered as a map and need exactly one label"))),
+                    };
+
+                    if map.contains_key(&key) {
+                        return Err(Error::InvalidBlocks(format!("`{ident} \"{key}\"` is declared twice")));
+                    }
+
+                    map.insert(key, self.object(ident, block)?);
+                }
+
+                Ok(hcl::Value::Object(map))
+            }
+        }
+    }
+
+    /// The body of `block` as an object, with its labels added under their keys.
+    fn object(&self, ident: &str, block: Block) -> Result<hcl::Value, Error> {
+        let labels: Vec<String> = block.labels().iter().map(|label| label.as_str().to_owned()).collect();
+        let name = || std::iter::once(ident.to_owned()).chain(labels.iter().map(|label| format!("\"{label}\""))).collect::<Vec<_>>().join(" ");
+
+        if self.layout == Layout::List && labels.len() > self.labels.len() {
+            return Err(Error::InvalidBlocks(format!(
+                "`{}` has {} labels, but `meta.blocks.{ident}.label` names {}",
+                name(),
+                labels.len(),
+                self.labels.len()
+            )));
+        }
+
+        let hcl::Value::Object(body) = hcl::from_body(block.body)? else {
+            unreachable!("a body is an object")
+        };
+        let mut obj = hcl::Map::new();
+
+        for (key, label) in self.labels.iter().zip(&labels) {
+            if body.contains_key(key) {
+                return Err(Error::InvalidBlocks(format!("`{}` already has a `{key}`, which its label is rendered as", name())));
+            }
+
+            obj.insert(key.to_owned(), hcl::Value::from(label.to_owned()));
+        }
+
+        obj.extend(body);
+        Ok(hcl::Value::Object(obj))
+    }
+}
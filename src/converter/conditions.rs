@@ -0,0 +1,74 @@
+use crate::Error;
+
+use hcl::{
+    eval::{Context, Evaluate},
+    structure::{Attribute, Body, Structure},
+};
+
+/// Removes the blocks of `body` whose `when` attribute is false, and splices in the contents of the `when {}`
+/// blocks whose `condition` is true.
+///
+/// The content of `dynamic` blocks is left alone, it is checked for every block it generates.
+pub(crate) fn apply(body: Body, ctx: &Context) -> Result<Body, Error> {
+    let mut structures = Vec::new();
+
+    for structure in body {
+        let mut block = match structure {
+            Structure::Block(block) => block,
+            attribute => {
+                structures.push(attribute);
+                continue;
+            }
+        };
+
+        if block.identifier() == "when" && block.labels().is_empty() {
+            let (condition, rest) = take(block.body, "condition");
+            let condition = condition.ok_or_else(|| Error::InvalidCondition("`when` blocks need a `condition`".into()))?;
+
+            if holds(&condition, ctx)? {
+                structures.extend(apply(rest, ctx)?);
+            }
+
+            continue;
+        }
+
+        let (when, rest) = take(block.body, "when");
+        block.body = rest;
+
+        if let Some(when) = when {
+            if !holds(&when, ctx)? {
+                continue;
+            }
+        }
+
+        if block.identifier() != "dynamic" {
+            block.body = apply(block.body, ctx)?;
+        }
+
+        structures.push(Structure::Block(block));
+    }
+
+    Ok(Body(structures))
+}
+
+/// Splits the attribute `key` off `body`.
+fn take(body: Body, key: &str) -> (Option<Attribute>, Body) {
+    let mut found = None;
+    let mut structures = Vec::new();
+
+    for structure in body {
+        match structure {
+            Structure::Attribute(attr) if attr.key() == key && found.is_none() => found = Some(attr),
+            structure => structures.push(structure),
+        }
+    }
+
+    (found, Body(structures))
+}
+
+fn holds(condition: &Attribute, ctx: &Context) -> Result<bool, Error> {
+    match condition.expr().evaluate(ctx).map_err(hcl::Error::from)? {
+        hcl::Value::Bool(holds) => Ok(holds),
+        other => Err(Error::InvalidCondition(format!("`{}` needs to be a bool, got {other}", condition.key()))),
+    }
+}
@@ -0,0 +1,94 @@
+use super::conditions;
+use crate::Error;
+
+use hcl::{
+    eval::{Context, Evaluate},
+    structure::{Block, BlockLabel, Body, Structure},
+    Expression,
+};
+
+/// Replaces the `dynamic` blocks of `body` and the blocks nested in it with the blocks they generate.
+pub(crate) fn expand(body: Body, ctx: &Context) -> Result<Body, Error> {
+    let mut structures = Vec::new();
+
+    for structure in body {
+        match structure {
+            Structure::Block(block) if block.identifier() == "dynamic" => structures.extend(generate(block, ctx)?.into_iter().map(Structure::Block)),
+            Structure::Block(mut block) => {
+                block.body = expand(block.body, ctx)?;
+                structures.push(Structure::Block(block));
+            }
+            attribute => structures.push(attribute),
+        }
+    }
+
+    Ok(Body(structures))
+}
+
+/// Generates the blocks of a `dynamic "<type>" { for_each = <collection> content {} }` block.
+///
+/// Every element of `for_each` is bound to the iterator, named after the block type unless `iterator` renames it,
+/// as `{ key, value }` while `labels` and `content` are evaluated. Keys are the indices of lists and the keys of objects,
+/// and a `when` attribute in `content` skips the elements it is false for.
+fn generate(block: Block, ctx: &Context) -> Result<Vec<Block>, Error> {
+    let ident = match block.labels() {
+        [label] => label.as_str().to_owned(),
+        _ => return Err(Error::InvalidDynamic("Dynamic blocks need exactly one label, the type of the blocks to generate".into())),
+    };
+
+    let attribute = |key: &str| block.body().attributes().find(|attr| attr.key() == key).map(|attr| attr.expr().to_owned());
+    let for_each = attribute("for_each").ok_or_else(|| Error::InvalidDynamic(format!("Dynamic block '{ident}' has no `for_each`")))?;
+
+    let iterator = match attribute("iterator") {
+        None => ident.to_owned(),
+        Some(Expression::Variable(name)) => name.to_string(),
+        Some(Expression::String(name)) => name,
+        Some(_) => return Err(Error::InvalidDynamic(format!("Dynamic block '{ident}' needs `iterator` to be a name"))),
+    };
+
+    let content = match block.body().blocks().filter(|block| block.identifier() == "content").collect::<Vec<_>>().as_slice() {
+        [content] => content.body().to_owned(),
+        _ => return Err(Error::InvalidDynamic(format!("Dynamic block '{ident}' needs exactly one `content` block"))),
+    };
+
+    let elements: Vec<(hcl::Value, hcl::Value)> = match for_each.evaluate(ctx).map_err(hcl::Error::from)? {
+        hcl::Value::Null => Vec::new(),
+        hcl::Value::Array(items) => items.into_iter().enumerate().map(|(index, item)| (hcl::Value::from(index), item)).collect(),
+        hcl::Value::Object(entries) => entries.into_iter().map(|(key, value)| (hcl::Value::from(key), value)).collect(),
+        other => return Err(Error::InvalidDynamic(format!("Dynamic block '{ident}' needs `for_each` to be a list or an object, got {other}"))),
+    };
+
+    let mut blocks = Vec::new();
+
+    for (key, value) in elements {
+        let mut scope = ctx.clone();
+        scope.declare_var(iterator.as_str(), hcl::Value::from_iter([("key", key), ("value", value)]));
+
+        let labels = match attribute("labels").map(|labels| labels.evaluate(&scope)).transpose().map_err(hcl::Error::from)? {
+            None => Vec::new(),
+            Some(hcl::Value::Array(labels)) => labels.into_iter().map(|label| label_of(&ident, label)).collect::<Result<_, _>>()?,
+            Some(_) => return Err(Error::InvalidDynamic(format!("Dynamic block '{ident}' needs `labels` to be a list"))),
+        };
+
+        let block = Block {
+            identifier: ident.as_str().into(),
+            labels,
+            body: content.to_owned(),
+        };
+
+        // nested dynamic blocks see the iterators of the blocks around them
+        let generated = conditions::apply(Body(vec![Structure::Block(block)]), &scope)?;
+        let generated = expand(generated, &scope)?.evaluate(&scope).map_err(hcl::Error::from)?;
+        blocks.extend(generated.into_blocks());
+    }
+
+    Ok(blocks)
+}
+
+fn label_of(ident: &str, label: hcl::Value) -> Result<BlockLabel, Error> {
+    match label {
+        hcl::Value::String(label) => Ok(BlockLabel::String(label)),
+        hcl::Value::Number(number) => Ok(BlockLabel::String(number.to_string())),
+        other => Err(Error::InvalidDynamic(format!("Dynamic block '{ident}' has a label that is not a string: {other}"))),
+    }
+}
@@ -0,0 +1,182 @@
+use crate::Error;
+use std::str::FromStr;
+
+/// How a list from the extending document is combined with the list it overrides.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Strategy {
+    /// The child's list replaces the parent's.
+    Replace,
+    /// The child's items are added after the parent's.
+    Append,
+    /// Objects sharing the value of the key are merged, other items are appended.
+    Merge(String),
+}
+
+impl FromStr for Strategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "replace" => Ok(Strategy::Replace),
+            None if s == "append" => Ok(Strategy::Append),
+            Some(("merge", key)) if !key.is_empty() => Ok(Strategy::Merge(key.to_owned())),
+            _ => Err(Error::InvalidExtends(format!("Unknown list strategy '{s}', expected replace, append or merge:<key>"))),
+        }
+    }
+}
+
+/// The list strategies from `meta.lists`, by dotted path with `*` as the default.
+#[derive(Clone, Debug)]
+pub(crate) struct Lists {
+    default: Strategy,
+    paths: hcl::Map<String, Strategy>,
+}
+
+impl Lists {
+    pub(crate) fn parse(value: Option<&hcl::Value>) -> Result<Self, Error> {
+        let mut lists = Lists {
+            default: Strategy::Replace,
+            paths: hcl::Map::new(),
+        };
+
+        match value {
+            None => {}
+            Some(hcl::Value::String(strategy)) => lists.default = strategy.parse()?,
+            Some(hcl::Value::Object(paths)) => {
+                for (path, strategy) in paths {
+                    let strategy = strategy
+                        .as_str()
+                        .ok_or_else(|| Error::InvalidExtends(format!("List strategy for '{path}' needs to be a string")))?
+                        .parse()?;
+
+                    match path.as_str() {
+                        "*" => lists.default = strategy,
+                        _ => _ = lists.paths.insert(path.to_owned(), strategy),
+                    }
+                }
+            }
+            Some(_) => return Err(Error::InvalidExtends("`meta.lists` needs to be a strategy or an object of strategies".into())),
+        }
+
+        Ok(lists)
+    }
+
+    fn strategy(&self, path: &[String]) -> &Strategy { self.paths.get(&path.join(".")).unwrap_or(&self.default) }
+
+    /// Deep-merges `child` over `parent`, removing the keys the child sets to `null`.
+    pub(crate) fn merge(&self, parent: hcl::Value, child: hcl::Value) -> hcl::Value { self.merge_at(&mut Vec::new(), parent, child) }
+
+    fn merge_at(&self, path: &mut Vec<String>, parent: hcl::Value, child: hcl::Value) -> hcl::Value {
+        match (parent, child) {
+            (hcl::Value::Object(mut parent), hcl::Value::Object(child)) => {
+                for (key, value) in child {
+                    if value.is_null() {
+                        parent.shift_remove(&key);
+                        continue;
+                    }
+
+                    path.push(key.to_owned());
+                    // merging into an empty object drops the nulls of keys the parent doesn't have
+                    let base = parent.get(&key).cloned().unwrap_or(hcl::Value::Object(hcl::Map::new()));
+                    let merged = self.merge_at(path, base, value);
+                    path.pop();
+
+                    parent.insert(key, merged);
+                }
+
+                hcl::Value::Object(parent)
+            }
+            (hcl::Value::Array(mut parent), hcl::Value::Array(child)) => match self.strategy(path) {
+                Strategy::Replace => hcl::Value::Array(child),
+                Strategy::Append => {
+                    parent.extend(child);
+                    hcl::Value::Array(parent)
+                }
+                Strategy::Merge(key) => {
+                    for item in child {
+                        let id = item.as_object().and_then(|obj| obj.get(key)).filter(|id| !id.is_null());
+                        let existing = id.and_then(|id| parent.iter().position(|other| other.as_object().and_then(|obj| obj.get(key)) == Some(id)));
+
+                        match existing {
+                            Some(index) => parent[index] = self.merge_at(path, parent[index].to_owned(), item),
+                            None => parent.push(item),
+                        }
+                    }
+
+                    hcl::Value::Array(parent)
+                }
+            },
+            (_, child) => child,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lists, Strategy};
+    use crate::Error;
+
+    fn value(source: &str) -> hcl::Value { hcl::from_str(source).unwrap() }
+
+    fn merge(lists: &str, parent: &str, child: &str) -> hcl::Value {
+        let lists = Lists::parse(value(lists).as_object().unwrap().get("lists")).unwrap();
+        lists.merge(value(parent), value(child))
+    }
+
+    #[test]
+    fn parses_strategies() {
+        assert_eq!("replace".parse::<Strategy>().unwrap(), Strategy::Replace);
+        assert_eq!("append".parse::<Strategy>().unwrap(), Strategy::Append);
+        assert_eq!("merge:name".parse::<Strategy>().unwrap(), Strategy::Merge("name".into()));
+        assert!(matches!("merge:".parse::<Strategy>(), Err(Error::InvalidExtends(_))));
+        assert!(matches!("prepend".parse::<Strategy>(), Err(Error::InvalidExtends(_))));
+    }
+
+    #[test]
+    fn replaces_lists_by_default() {
+        assert_eq!(merge("", "ports = [80, 443]", "ports = [8080]"), value("ports = [8080]"));
+    }
+
+    #[test]
+    fn appends_lists() {
+        assert_eq!(merge("lists = \"append\"", "ports = [80]", "ports = [443]"), value("ports = [80, 443]"));
+    }
+
+    #[test]
+    fn merges_lists_by_key() {
+        let parent = "containers = [{ name = \"app\", image = \"app:1\", port = 80 }, { name = \"sidecar\", image = \"proxy\" }]";
+        let child = "containers = [{ name = \"app\", image = \"app:2\" }, { name = \"worker\", image = \"app:2\" }, { image = \"unnamed\" }]";
+        let expected = "containers = [{ name = \"app\", image = \"app:2\", port = 80 }, { name = \"sidecar\", image = \"proxy\" }, { name = \"worker\", image = \"app:2\" }, { image = \"unnamed\" }]";
+
+        assert_eq!(merge("lists = { containers = \"merge:name\" }", parent, child), value(expected));
+    }
+
+    #[test]
+    fn strategies_apply_by_path() {
+        let lists = "lists = { \"*\" = \"append\", \"spec.args\" = \"replace\" }";
+        let merged = merge(lists, "spec = { args = [\"-v\"], env = [\"A=1\"] }", "spec = { args = [\"-q\"], env = [\"B=2\"] }");
+
+        assert_eq!(merged, value("spec = { args = [\"-q\"], env = [\"A=1\", \"B=2\"] }"));
+    }
+
+    #[test]
+    fn null_removes_keys() {
+        let merged = merge(
+            "",
+            "server = { host = \"localhost\", port = 80, tls = { cert = \"a.pem\" } }\ndebug = true",
+            "server = { tls = null, port = 8080 }\ndebug = null",
+        );
+        assert_eq!(merged, value("server = { host = \"localhost\", port = 8080 }"));
+    }
+
+    #[test]
+    fn null_is_dropped_from_new_keys() {
+        assert_eq!(merge("", "a = 1", "b = { c = null, d = 2 }"), value("a = 1\nb = { d = 2 }"));
+    }
+
+    #[test]
+    fn rejects_invalid_lists() {
+        assert!(matches!(Lists::parse(Some(&hcl::Value::from(1))), Err(Error::InvalidExtends(_))));
+        assert!(matches!(Lists::parse(value("lists = { a = 1 }").as_object().unwrap().get("lists")), Err(Error::InvalidExtends(_))));
+    }
+}
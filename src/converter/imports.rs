@@ -0,0 +1,65 @@
+use crate::Error;
+
+use hcl::{structure::Body, Expression};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+/// Files being imported by the current render, and the output of those already evaluated.
+#[derive(Clone, Default)]
+pub(crate) struct Imports {
+    /// Canonical paths from the rendered document down to the current one.
+    pub(crate) chain: Vec<PathBuf>,
+    pub(crate) cache: Rc<RefCell<HashMap<PathBuf, hcl::Value>>>,
+}
+
+/// Reads the `import "name" { source = "path" }` blocks of `body`.
+pub(crate) fn collect(body: &Body) -> Result<Vec<(String, String)>, Error> {
+    let mut imports: Vec<(String, String)> = Vec::new();
+
+    for block in body.blocks().filter(|block| block.identifier() == "import") {
+        let name = match block.labels() {
+            [label] => label.as_str().to_owned(),
+            _ => return Err(Error::InvalidImport("Import blocks need exactly one label".into())),
+        };
+
+        let source = match block.body().attributes().find(|attr| attr.key() == "source").map(|attr| attr.expr()) {
+            Some(Expression::String(source)) => source.to_owned(),
+            Some(_) => return Err(Error::InvalidImport(format!("Import '{name}' needs `source` to be a string"))),
+            None => return Err(Error::InvalidImport(format!("Import '{name}' has no `source`"))),
+        };
+
+        if imports.iter().any(|(existing, _)| *existing == name) {
+            return Err(Error::InvalidImport(format!("Import '{name}' is declared more than once")));
+        }
+
+        imports.push((name, source));
+    }
+
+    Ok(imports)
+}
+
+impl Imports {
+    /// The chain continued with `path`, or the files forming a cycle relative to `root`.
+    pub(crate) fn enter(&self, path: &Path, root: &Path) -> Result<Self, Error> {
+        let mut chain = self.chain.clone();
+        chain.push(path.to_owned());
+
+        if let Some(start) = self.chain.iter().position(|entry| entry == path) {
+            let root = root.canonicalize()?;
+
+            return Err(Error::ImportCycle(
+                chain[start..].iter().map(|entry| entry.strip_prefix(&root).unwrap_or(entry).display().to_string()).collect(),
+            ));
+        }
+
+        Ok(Self { chain, cache: self.cache.clone() })
+    }
+
+    pub(crate) fn cached(&self, path: &Path) -> Option<hcl::Value> { self.cache.borrow().get(path).cloned() }
+
+    pub(crate) fn store(&self, path: PathBuf, value: hcl::Value) { self.cache.borrow_mut().insert(path, value); }
+}
@@ -0,0 +1,230 @@
+use hcl::edit::{
+    expr::{Expression, FuncCall, Traversal, TraversalOperator},
+    structure::{Body, Structure},
+    visit::{self, Visit},
+};
+use std::collections::{HashMap, HashSet};
+
+/// The namespaces entries of variable blocks and `locals` are declared in.
+pub(crate) const NAMESPACES: [&str; 2] = ["var", "local"];
+
+/// The source of a `var` or `local` entry.
+pub(crate) enum Entry<'a> {
+    /// Attributes or blocks evaluated against the context.
+    Source(Vec<&'a Structure>),
+    /// The `default` of a `variable` block.
+    Default(&'a Expression),
+    /// A value set by the caller, such as a variable override.
+    Value(hcl::Value),
+}
+
+/// References from an expression to `var`/`local` entries and to other functions.
+#[derive(Default, Clone)]
+pub(crate) struct References {
+    /// `(namespace, key)`, without a key when the whole namespace is used.
+    entries: Vec<(&'static str, Option<String>)>,
+    calls: Vec<String>,
+}
+
+impl Visit for References {
+    fn visit_expr(&mut self, node: &Expression) {
+        match node {
+            Expression::Variable(name) => {
+                if let Some(namespace) = namespace(name.as_str()) {
+                    self.entries.push((namespace, None));
+                }
+            }
+            _ => visit::visit_expr(self, node),
+        }
+    }
+
+    fn visit_traversal(&mut self, node: &Traversal) {
+        let root = match &node.expr {
+            Expression::Variable(name) => namespace(name.as_str()),
+            _ => None,
+        };
+
+        let Some(namespace) = root else {
+            return visit::visit_traversal(self, node);
+        };
+
+        let key = match node.operators.first().map(|op| op.value()) {
+            Some(TraversalOperator::GetAttr(key)) => Some(key.as_str().to_owned()),
+            Some(TraversalOperator::Index(Expression::String(key))) => Some(key.as_str().to_owned()),
+            _ => None,
+        };
+
+        self.entries.push((namespace, key));
+
+        for op in &node.operators {
+            self.visit_traversal_operator(op);
+        }
+    }
+
+    fn visit_func_call(&mut self, node: &FuncCall) {
+        if node.name.namespace.is_empty() {
+            self.calls.push(node.name.name.as_str().to_owned());
+        }
+
+        visit::visit_func_call(self, node);
+    }
+}
+
+impl References {
+    pub(crate) fn of(structures: &[&Structure]) -> Self {
+        let mut references = Self::default();
+        structures.iter().for_each(|structure| references.visit_structure(structure));
+        references
+    }
+
+    pub(crate) fn of_expression(expr: &Expression) -> Self {
+        let mut references = Self::default();
+        references.visit_expr(expr);
+        references
+    }
+
+    /// Adds the references made by the bodies of the `function` blocks it calls, transitively.
+    pub(crate) fn through(mut self, functions: &HashMap<String, References>) -> Self {
+        let mut seen = HashSet::new();
+        let mut pending = std::mem::take(&mut self.calls);
+
+        while let Some(name) = pending.pop() {
+            if let Some(body) = functions.get(&name).filter(|_| seen.insert(name.to_owned())) {
+                self.entries.extend(body.entries.iter().cloned());
+                pending.extend(body.calls.iter().cloned());
+            }
+        }
+
+        self
+    }
+}
+
+/// The references of each `function` block's `result`.
+pub(crate) fn functions(body: &Body) -> HashMap<String, References> {
+    body.get_blocks("function")
+        .filter_map(|block| {
+            let [label] = &block.labels[..] else { return None };
+            let result = block.body.get_attribute("result")?;
+            Some((label.as_str().to_owned(), References::of_expression(&result.value)))
+        })
+        .collect()
+}
+
+/// Groups the structures of every unlabelled `name` block by their key.
+pub(crate) fn entries<'a>(body: &'a Body, name: &'a str) -> Option<hcl::Map<String, Vec<&'a Structure>>> {
+    let mut entries: Option<hcl::Map<String, Vec<&Structure>>> = None;
+
+    for block in body.get_blocks(name).filter(|block| block.labels.is_empty()) {
+        let entries = entries.get_or_insert_with(hcl::Map::new);
+
+        for structure in block.body.iter() {
+            let key = match structure {
+                Structure::Attribute(attr) => attr.key.as_str(),
+                Structure::Block(block) => block.ident.as_str(),
+            };
+
+            entries.entry(key.to_owned()).or_default().push(structure);
+        }
+    }
+
+    entries
+}
+
+/// Orders `nodes` so every node comes after its dependencies, or returns the keys forming a cycle.
+pub(crate) fn order(nodes: &hcl::Map<String, Vec<String>>) -> Result<Vec<String>, Vec<String>> {
+    fn visit(key: &str, nodes: &hcl::Map<String, Vec<String>>, stack: &mut Vec<String>, done: &mut HashSet<String>, sorted: &mut Vec<String>) -> Result<(), Vec<String>> {
+        if done.contains(key) {
+            return Ok(());
+        }
+
+        if let Some(start) = stack.iter().position(|entry| entry == key) {
+            let mut cycle = stack[start..].to_vec();
+            cycle.push(key.to_owned());
+            return Err(cycle);
+        }
+
+        stack.push(key.to_owned());
+
+        for dependency in nodes.get(key).into_iter().flatten() {
+            visit(dependency, nodes, stack, done, sorted)?;
+        }
+
+        stack.pop();
+        done.insert(key.to_owned());
+        sorted.push(key.to_owned());
+
+        Ok(())
+    }
+
+    let (mut done, mut sorted) = (HashSet::new(), Vec::new());
+
+    for key in nodes.keys() {
+        visit(key, nodes, &mut Vec::new(), &mut done, &mut sorted)?;
+    }
+
+    Ok(sorted)
+}
+
+/// Resolves references to the node keys (`var.name`) they depend on.
+pub(crate) fn dependencies(references: &References, nodes: &hcl::Map<String, impl Sized>) -> Vec<String> {
+    let mut dependencies: Vec<String> = Vec::new();
+
+    for (namespace, key) in &references.entries {
+        match key {
+            Some(key) => dependencies.push(format!("{namespace}.{key}")),
+            None => dependencies.extend(nodes.keys().filter(|node| node.strip_prefix(namespace).is_some_and(|rest| rest.starts_with('.'))).cloned()),
+        }
+    }
+
+    dependencies.retain(|dependency| nodes.contains_key(dependency));
+    dependencies.dedup();
+    dependencies
+}
+
+fn namespace(name: &str) -> Option<&'static str> { NAMESPACES.into_iter().find(|namespace| *namespace == name) }
+
+#[cfg(test)]
+mod tests {
+    use super::order;
+    use crate::{Error, HclConverter};
+
+    fn graph(edges: &[(&str, &[&str])]) -> hcl::Map<String, Vec<String>> {
+        edges
+            .iter()
+            .map(|(key, dependencies)| (key.to_string(), dependencies.iter().map(|dependency| dependency.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn orders_dependencies_first() {
+        let sorted = order(&graph(&[("local.c", &["local.b"]), ("local.b", &["local.a", "var.x"]), ("local.a", &[]), ("var.x", &[])])).unwrap();
+        let position = |key: &str| sorted.iter().position(|entry| entry == key).unwrap();
+
+        assert_eq!(sorted.len(), 4);
+        assert!(position("local.a") < position("local.b"));
+        assert!(position("var.x") < position("local.b"));
+        assert!(position("local.b") < position("local.c"));
+    }
+
+    #[test]
+    fn reports_the_cycle() {
+        let cycle = order(&graph(&[("local.a", &["local.b"]), ("local.b", &["local.c"]), ("local.c", &["local.a"]), ("local.d", &[])])).unwrap_err();
+        assert_eq!(cycle, ["local.a", "local.b", "local.c", "local.a"]);
+    }
+
+    #[test]
+    fn locals_reference_later_locals() {
+        let source = "meta {}\n\nlocals {\n  url  = \"${local.host}:${var.port}\"\n  host = \"localhost\"\n}\n\nvariable \"port\" {\n  default = 8080\n}\n\nurl = local.url\n";
+        let value = HclConverter::builder().source(source).build().unwrap().value().unwrap();
+
+        assert_eq!(value.as_object().unwrap()["url"], hcl::Value::from("localhost:8080"));
+    }
+
+    #[test]
+    fn cyclic_locals_fail() {
+        let source = "meta {}\n\nlocals {\n  a = local.b\n  b = local.a\n}\n\nvalue = local.a\n";
+        let err = HclConverter::builder().source(source).build().and_then(|hcl| hcl.value()).unwrap_err();
+
+        assert!(matches!(err, Error::CyclicVariables(ref keys) if keys == &["local.a", "local.b", "local.a"]), "{err}");
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{Error, Language};
+
+use hcl::structure::{Body, Structure};
+use std::path::{Component, Path};
+
+/// A file rendered from a document, either the document itself or one of its `output` blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Artifact {
+    /// Label of the `output` block, `None` for the document itself.
+    pub name: Option<String>,
+    /// File name with its extension, relative to the directory of the document.
+    pub file: String,
+    /// The rendered content.
+    pub data: String,
+}
+
+/// An evaluated `output "<name>" { file = "<file>" value = <value> }` block.
+pub(crate) struct Output {
+    pub(crate) name: String,
+    pub(crate) file: String,
+    pub(crate) value: hcl::Value,
+}
+
+impl Output {
+    /// Format from the extension of `file`, if it names one.
+    pub(crate) fn language(&self) -> Option<Language> {
+        match Path::new(&self.file).extension().map(|ext| Language::parse(&ext.to_string_lossy())) {
+            None | Some(Language::None) => None,
+            lang => lang,
+        }
+    }
+}
+
+/// Splits the `output` blocks off an evaluated body.
+pub(crate) fn split(body: Body) -> Result<(Body, Vec<Output>), Error> {
+    let mut structures = Vec::new();
+    let mut outputs: Vec<Output> = Vec::new();
+
+    for structure in body {
+        let block = match structure {
+            Structure::Block(block) if block.identifier() == "output" => block,
+            structure => {
+                structures.push(structure);
+                continue;
+            }
+        };
+
+        let name = match block.labels() {
+            [label] => label.as_str().to_owned(),
+            _ => return Err(Error::InvalidOutput("Output blocks need exactly one label, their name".into())),
+        };
+
+        if outputs.iter().any(|output| output.name == name) {
+            return Err(Error::InvalidOutput(format!("Output '{name}' is declared twice")));
+        }
+
+        let hcl::Value::Object(mut attrs) = hcl::from_body(block.body)? else {
+            unreachable!("a body is an object")
+        };
+
+        let file = match attrs.shift_remove("file") {
+            Some(hcl::Value::String(file)) => file,
+            Some(other) => return Err(Error::InvalidOutput(format!("Output '{name}' needs `file` to be a string, got {other}"))),
+            None => return Err(Error::InvalidOutput(format!("Output '{name}' has no `file`"))),
+        };
+
+        if file.is_empty() || Path::new(&file).components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(Error::InvalidOutput(format!(
+                "Output '{name}' needs `file` to be a relative path inside the output directory, got '{file}'"
+            )));
+        }
+
+        let value = attrs.shift_remove("value").ok_or_else(|| Error::InvalidOutput(format!("Output '{name}' has no `value`")))?;
+
+        if let Some(key) = attrs.keys().next() {
+            return Err(Error::InvalidOutput(format!("Output '{name}' has an unknown attribute `{key}`, only `file` and `value` are allowed")));
+        }
+
+        outputs.push(Output { name, file, value });
+    }
+
+    Ok((Body(structures), outputs))
+}
@@ -0,0 +1,206 @@
+use crate::{macros::slots, sensitive, Error};
+
+use hcl::{
+    eval::{Context, Evaluate, Func, FuncArgs, FuncDef, ParamType},
+    expr::{ForExpr, Traversal, TraversalOperator},
+    structure::Body,
+    Expression, Identifier,
+};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+/// Nested calls of document functions allowed before a call fails.
+pub(crate) const RECURSION_LIMIT: usize = 64;
+
+static SLOTS: [[Func; 16]; 16] = slots!(slot);
+
+thread_local! {
+    static SCOPE: RefCell<Option<Scope>> = const { RefCell::new(None) };
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    static EXCEEDED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// A function declared with a `function "name" { params = [...] result = <expr> }` block.
+#[derive(Clone)]
+pub(crate) struct UserFunc {
+    name: String,
+    params: Vec<String>,
+    result: Expression,
+}
+
+/// The functions of the document being evaluated and the context their bodies run in.
+struct Scope {
+    funcs: Rc<[UserFunc]>,
+    ctx: Rc<Context<'static>>,
+}
+
+impl UserFunc {
+    pub(crate) fn name(&self) -> &str { &self.name }
+}
+
+/// Reads the top-level `function` blocks of `body`.
+pub(crate) fn collect(body: &Body) -> Result<Vec<UserFunc>, Error> {
+    let mut funcs: Vec<UserFunc> = Vec::new();
+
+    for block in body.blocks().filter(|block| block.identifier() == "function") {
+        let name = match block.labels() {
+            [label] => label.as_str().to_owned(),
+            _ => return Err(Error::InvalidFunction("Function blocks need exactly one label".into())),
+        };
+
+        let invalid = |reason: &str| Error::InvalidFunction(format!("Function '{name}' {reason}"));
+
+        if Identifier::new(name.as_str()).is_err() {
+            return Err(invalid("is not a valid function name"));
+        }
+
+        if funcs.iter().any(|func| func.name == name) {
+            return Err(invalid("is declared more than once"));
+        }
+
+        let params = match block.body().attributes().find(|attr| attr.key() == "params").map(|attr| attr.expr()) {
+            None => Vec::new(),
+            Some(Expression::Array(params)) => params
+                .iter()
+                .map(|param| match param {
+                    Expression::String(param) => Identifier::new(param.as_str()).map(|_| param.to_owned()).ok(),
+                    Expression::Variable(param) => Some(param.to_string()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<String>>>()
+                .ok_or_else(|| invalid("has a parameter that is not a name"))?,
+            Some(_) => return Err(invalid("needs `params` to be a list of names")),
+        };
+
+        let result = block
+            .body()
+            .attributes()
+            .find(|attr| attr.key() == "result")
+            .map(|attr| attr.expr().to_owned())
+            .ok_or_else(|| invalid("has no `result`"))?;
+
+        funcs.push(UserFunc { name, params, result });
+    }
+
+    if funcs.len() > 16 * 16 {
+        return Err(Error::InvalidFunction(format!("At most {} functions can be declared in a document", 16 * 16)));
+    }
+
+    Ok(funcs)
+}
+
+pub(crate) fn declare(ctx: &mut Context, funcs: &[UserFunc]) {
+    for (index, func) in funcs.iter().enumerate() {
+        let def = func.params.iter().fold(FuncDef::builder(), |def, _| def.param(ParamType::Any));
+        ctx.declare_func(func.name.as_str(), def.build(SLOTS[index / 16][index % 16]));
+    }
+}
+
+/// Makes `funcs` callable while `f` runs, their bodies are evaluated in the context from `base`.
+pub(crate) fn scoped<R>(funcs: &[UserFunc], base: impl FnOnce() -> Context<'static>, f: impl FnOnce() -> R) -> R {
+    if funcs.is_empty() {
+        return f();
+    }
+
+    let scope = Scope {
+        funcs: funcs.into(),
+        ctx: Rc::new(base()),
+    };
+    let previous = SCOPE.replace(Some(scope));
+    let result = f();
+
+    SCOPE.set(previous);
+    result
+}
+
+/// Whether a function call failed with `message` because it exceeded [`RECURSION_LIMIT`].
+pub(crate) fn exceeded(message: &str) -> bool { message.ends_with(&format!("exceeded the recursion limit of {RECURSION_LIMIT}")) }
+
+fn call(index: usize, args: &[hcl::Value]) -> Result<hcl::Value, String> {
+    let (funcs, ctx) = SCOPE
+        .with_borrow(|scope| scope.as_ref().map(|scope| (scope.funcs.clone(), scope.ctx.clone())))
+        .ok_or("function called outside of its document")?;
+    let func = &funcs[index];
+    let depth = DEPTH.get();
+
+    if depth >= RECURSION_LIMIT {
+        EXCEEDED.set(true);
+        return Err(format!("function `{}` exceeded the recursion limit of {RECURSION_LIMIT}", func.name));
+    }
+
+    // `for` expressions bind the parameters in a child of the context, which is cheaper than a copy of it
+    let result = func.params.iter().zip(args).rev().fold(func.result.to_owned(), |result, (param, arg)| bind(param, arg, result));
+
+    DEPTH.set(depth + 1);
+    let result = result.evaluate(&ctx);
+    DEPTH.set(depth);
+
+    let exceeded = EXCEEDED.get();
+
+    if depth == 0 {
+        EXCEEDED.set(false);
+    }
+
+    match result {
+        Ok(value) => Ok(value),
+        // report the innermost call once instead of once per level
+        Err(_) if exceeded => Err(format!("function `{}` exceeded the recursion limit of {RECURSION_LIMIT}", func.name)),
+        // errors at the top of the body name the `for` binding the parameters, rather than the body
+        Err(err) => match err.expr() {
+            Some(Expression::ForExpr(binding)) if binding.value_expr == func.result => Err(format!("{} in expression `{}`", err.kind(), func.result)),
+            _ => Err(err.to_string()),
+        },
+    }
+}
+
+/// `[for <param> in [<arg>] : <result>][0]`, the result with the parameter bound to the argument.
+fn bind(param: &str, arg: &hcl::Value, result: Expression) -> Expression {
+    let binding = ForExpr::new(Identifier::unchecked(param), Expression::Array(vec![Expression::from(arg.to_owned())]), result);
+    Expression::from(Traversal::new(binding, [TraversalOperator::Index(Expression::from(0))]))
+}
+
+fn slot<const N: usize>(args: FuncArgs) -> Result<hcl::Value, String> { sensitive::call(|args| call(N, &args), args) }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, HclConverter};
+
+    fn value(source: &str) -> Result<hcl::Value, Error> {
+        let value = HclConverter::builder().source(format!("meta {{}}\n\n{source}")).build()?.value()?;
+        Ok(value.as_object().unwrap()["value"].to_owned())
+    }
+
+    #[test]
+    fn binds_parameters() {
+        let source = "function \"greet\" {\n  params = [name, greeting]\n  result = \"${greeting}, ${name}\"\n}\n\nvalue = greet(\"ship\", \"hello\")\n";
+        assert_eq!(value(source).unwrap(), hcl::Value::from("hello, ship"));
+    }
+
+    #[test]
+    fn recurses() {
+        let source = "function \"factorial\" {\n  params = [n]\n  result = (n <= 1) ? 1 : n * factorial(n - 1)\n}\n\nvalue = factorial(10)\n";
+        assert_eq!(value(source).unwrap(), hcl::Value::from(3628800));
+    }
+
+    #[test]
+    fn parameters_do_not_leak_into_calls() {
+        let source = "function \"inner\" {\n  result = n\n}\n\nfunction \"outer\" {\n  params = [n]\n  result = inner()\n}\n\nvalue = outer(1)\n";
+        assert!(value(source).is_err());
+    }
+
+    #[test]
+    fn errors_name_the_body() {
+        let err = value("function \"get\" {\n  params = [obj]\n  result = obj.missing\n}\n\nvalue = get({ a = 1 })\n").unwrap_err();
+        assert!(err.to_string().contains("no such key: `missing` in expression `obj.missing`"), "{err}");
+    }
+
+    #[test]
+    fn recursion_limit_is_a_document_error() {
+        let err = value("function \"loop\" {\n  params = [n]\n  result = loop(n)\n}\n\nvalue = loop(1)\n").unwrap_err();
+
+        assert_eq!(err.status(), 422);
+        assert!(err.to_string().contains("function `loop` exceeded the recursion limit of 64"));
+    }
+}
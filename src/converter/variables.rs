@@ -0,0 +1,315 @@
+use crate::{numbers, Error};
+
+use hcl::{
+    edit::{expr::Expression as EditExpression, structure::Body},
+    eval::{Context, Evaluate},
+    Expression, Identifier, ObjectKey,
+};
+use std::fmt;
+
+/// A type constraint from the `type` attribute of a `variable` block.
+#[derive(Clone, Debug)]
+pub(crate) enum Type {
+    Any,
+    String,
+    Number,
+    Bool,
+    List(Box<Type>),
+    Set(Box<Type>),
+    Map(Box<Type>),
+    Tuple(Vec<Type>),
+    Object(Vec<Attribute>),
+}
+
+/// An attribute of an `object({...})` type, `optional(type, default)` ones may be left out.
+#[derive(Clone, Debug)]
+pub(crate) struct Attribute {
+    name: String,
+    ty: Type,
+    optional: Option<hcl::Value>,
+}
+
+/// A condition from a `validation` block and the message reported when it is false.
+pub(crate) struct Validation {
+    pub(crate) condition: Expression,
+    pub(crate) message: Expression,
+}
+
+/// A `variable "name" { type = ..., default = ..., validation { ... } }` block.
+pub(crate) struct Variable<'a> {
+    pub(crate) name: String,
+    pub(crate) default: Option<&'a EditExpression>,
+    pub(crate) validations: Vec<Validation>,
+    ty: Type,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Any => write!(f, "any"),
+            Type::String => write!(f, "string"),
+            Type::Number => write!(f, "number"),
+            Type::Bool => write!(f, "bool"),
+            Type::List(ty) => write!(f, "list({ty})"),
+            Type::Set(ty) => write!(f, "set({ty})"),
+            Type::Map(ty) => write!(f, "map({ty})"),
+            Type::Tuple(types) => write!(f, "tuple([{}])", types.iter().map(Type::to_string).collect::<Vec<_>>().join(", ")),
+            Type::Object(attrs) => write!(f, "object({{{}}})", attrs.iter().map(|attr| format!("{} = {}", attr.name, attr.ty)).collect::<Vec<_>>().join(", ")),
+        }
+    }
+}
+
+impl Type {
+    fn parse(expr: &Expression) -> Result<Self, String> {
+        let invalid = || format!("has an invalid type `{}`", hcl::format::to_string(expr).unwrap_or_default());
+
+        let call = match expr {
+            Expression::Variable(name) => {
+                return match name.as_str() {
+                    "any" => Ok(Type::Any),
+                    "string" => Ok(Type::String),
+                    "number" => Ok(Type::Number),
+                    "bool" | "boolean" => Ok(Type::Bool),
+                    _ => Err(invalid()),
+                }
+            }
+            Expression::FuncCall(call) if call.name.namespace.is_empty() => call,
+            _ => return Err(invalid()),
+        };
+
+        match (call.name.name.as_str(), &call.args[..]) {
+            ("list", [ty]) => Ok(Type::List(Box::new(Type::parse(ty)?))),
+            ("set", [ty]) => Ok(Type::Set(Box::new(Type::parse(ty)?))),
+            ("map", [ty]) => Ok(Type::Map(Box::new(Type::parse(ty)?))),
+            ("tuple", [Expression::Array(types)]) => Ok(Type::Tuple(types.iter().map(Type::parse).collect::<Result<_, _>>()?)),
+            ("object", [Expression::Object(attrs)]) => {
+                let mut attributes = Vec::new();
+
+                for (key, expr) in attrs {
+                    let name = match key {
+                        ObjectKey::Identifier(name) => name.to_string(),
+                        ObjectKey::Expression(Expression::String(name)) => name.to_owned(),
+                        _ => return Err(invalid()),
+                    };
+
+                    let (ty, optional) = match expr {
+                        Expression::FuncCall(call) if call.name.namespace.is_empty() && call.name.name.as_str() == "optional" => match &call.args[..] {
+                            [ty] => (Type::parse(ty)?, Some(hcl::Value::Null)),
+                            [ty, default] => (Type::parse(ty)?, Some(default.evaluate(&Context::new()).map_err(|_| invalid())?)),
+                            _ => return Err(invalid()),
+                        },
+                        expr => (Type::parse(expr)?, None),
+                    };
+
+                    attributes.push(Attribute { name, ty, optional });
+                }
+
+                Ok(Type::Object(attributes))
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Converts `value` to this type the way Terraform does, so `"8080"` is accepted as a number.
+    pub(crate) fn convert(&self, value: hcl::Value) -> Result<hcl::Value, String> {
+        match (self, value) {
+            (Type::Any, value) | (_, value @ hcl::Value::Null) => Ok(value),
+            (Type::String, value @ hcl::Value::String(_)) => Ok(value),
+            (Type::String, hcl::Value::Number(number)) => Ok(hcl::Value::String(number.to_string())),
+            (Type::String, hcl::Value::Bool(bool)) => Ok(hcl::Value::String(bool.to_string())),
+            (Type::Number, value @ hcl::Value::Number(_)) => Ok(value),
+            (Type::Number, hcl::Value::String(s)) => numbers::parse(&s).ok_or_else(|| format!("expected number, got \"{s}\"")),
+            (Type::Bool, value @ hcl::Value::Bool(_)) => Ok(value),
+            (Type::Bool, hcl::Value::String(s)) => match s.as_str() {
+                "true" => Ok(hcl::Value::Bool(true)),
+                "false" => Ok(hcl::Value::Bool(false)),
+                _ => Err(format!("expected bool, got \"{s}\"")),
+            },
+            (Type::List(ty) | Type::Set(ty), hcl::Value::Array(items)) => {
+                let mut converted: Vec<hcl::Value> = Vec::new();
+
+                for (index, item) in items.into_iter().enumerate() {
+                    let item = ty.convert(item).map_err(|err| format!("element {index}: {err}"))?;
+
+                    if !matches!(self, Type::Set(_)) || !converted.contains(&item) {
+                        converted.push(item);
+                    }
+                }
+
+                Ok(hcl::Value::Array(converted))
+            }
+            (Type::Tuple(types), hcl::Value::Array(items)) if types.len() == items.len() => types
+                .iter()
+                .zip(items)
+                .enumerate()
+                .map(|(index, (ty, item))| ty.convert(item).map_err(|err| format!("element {index}: {err}")))
+                .collect::<Result<_, _>>()
+                .map(hcl::Value::Array),
+            (Type::Map(ty), hcl::Value::Object(entries)) => entries
+                .into_iter()
+                .map(|(key, item)| ty.convert(item).map(|item| (key.to_owned(), item)).map_err(|err| format!("key `{key}`: {err}")))
+                .collect::<Result<_, _>>()
+                .map(hcl::Value::Object),
+            (Type::Object(attrs), hcl::Value::Object(mut entries)) => {
+                let mut converted = hcl::Map::new();
+
+                for attr in attrs {
+                    let value = match (entries.shift_remove(&attr.name), &attr.optional) {
+                        (Some(value), _) => attr.ty.convert(value).map_err(|err| format!("attribute `{}`: {err}", attr.name))?,
+                        (None, Some(default)) => default.to_owned(),
+                        (None, None) => return Err(format!("attribute `{}` is required", attr.name)),
+                    };
+
+                    converted.insert(attr.name.to_owned(), value);
+                }
+
+                Ok(hcl::Value::Object(converted))
+            }
+            (_, value) => Err(format!("expected {self}, got {}", kind(&value))),
+        }
+    }
+}
+
+impl Variable<'_> {
+    /// Converts a value given to the variable to its declared type.
+    pub(crate) fn check(&self, value: hcl::Value) -> Result<hcl::Value, Error> { self.ty.convert(value).map_err(|message| Error::InvalidVariable { name: self.name.to_owned(), message }) }
+}
+
+/// Reads the top-level `variable` blocks of `body`.
+pub(crate) fn collect(body: &Body) -> Result<Vec<Variable<'_>>, Error> {
+    let mut variables: Vec<Variable> = Vec::new();
+
+    for block in body.get_blocks("variable") {
+        let name = match &block.labels[..] {
+            [label] => label.as_str().to_owned(),
+            _ => return Err(Error::InvalidDeclaration("Variable blocks need exactly one label".into())),
+        };
+
+        let invalid = |reason: &str| Error::InvalidDeclaration(format!("Variable '{name}' {reason}"));
+
+        if Identifier::new(name.as_str()).is_err() {
+            return Err(invalid("is not a valid variable name"));
+        }
+
+        if variables.iter().any(|variable| variable.name == name) {
+            return Err(invalid("is declared more than once"));
+        }
+
+        let ty = match block.body.get_attribute("type") {
+            Some(attr) => Type::parse(&Expression::from(attr.value.to_owned())).map_err(|reason| invalid(&reason))?,
+            None => Type::Any,
+        };
+
+        let mut validations = Vec::new();
+
+        for validation in block.body.get_blocks("validation") {
+            let attribute = |key: &str| validation.body.get_attribute(key).map(|attr| Expression::from(attr.value.to_owned()));
+
+            validations.push(Validation {
+                condition: attribute("condition").ok_or_else(|| invalid("has a validation without `condition`"))?,
+                message: attribute("error_message").ok_or_else(|| invalid("has a validation without `error_message`"))?,
+            });
+        }
+
+        let default = block.body.get_attribute("default").map(|attr| &attr.value);
+        variables.push(Variable { name, default, validations, ty });
+    }
+
+    Ok(variables)
+}
+
+fn kind(value: &hcl::Value) -> &'static str {
+    match value {
+        hcl::Value::Null => "null",
+        hcl::Value::Bool(_) => "bool",
+        hcl::Value::Number(_) => "number",
+        hcl::Value::String(_) => "string",
+        hcl::Value::Array(_) => "list",
+        hcl::Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::collect;
+    use crate::{Error, HclConverter};
+
+    fn convert(ty: &str, value: impl Into<hcl::Value>) -> Result<hcl::Value, Error> {
+        let body: hcl::edit::structure::Body = format!("variable \"value\" {{\n  type = {ty}\n}}\n").parse().unwrap();
+        collect(&body)?[0].check(value.into())
+    }
+
+    fn render(source: &str, variables: &[(&str, hcl::Value)]) -> Result<hcl::Value, Error> {
+        let builder = variables.iter().fold(HclConverter::builder().source(format!("meta {{}}\n\n{source}")), |builder, (name, value)| {
+            builder.variable(*name, value.to_owned())
+        });
+        Ok(builder.build()?.value()?.as_object().unwrap()["value"].to_owned())
+    }
+
+    #[test]
+    fn converts_strings() {
+        assert_eq!(convert("number", "8080").unwrap(), hcl::Value::from(8080));
+        assert_eq!(convert("number", "0.5").unwrap(), hcl::Value::from(0.5));
+        assert_eq!(convert("bool", "true").unwrap(), hcl::Value::from(true));
+        assert_eq!(convert("string", 42).unwrap(), hcl::Value::from("42"));
+        assert_eq!(convert("list(number)", hcl::value!(["1", 2])).unwrap(), hcl::value!([1, 2]));
+        assert_eq!(convert("set(string)", hcl::value!(["a", "b", "a"])).unwrap(), hcl::value!(["a", "b"]));
+        assert_eq!(convert("map(bool)", hcl::value!({ a = "false" })).unwrap(), hcl::value!({ a = false }));
+    }
+
+    #[test]
+    fn fills_optional_attributes() {
+        let ty = "object({ host = string, port = optional(number, 80), tls = optional(bool) })";
+
+        assert_eq!(
+            convert(ty, hcl::value!({ host = "localhost", extra = 1 })).unwrap(),
+            hcl::value!({ host = "localhost", port = 80, tls = null })
+        );
+        assert_eq!(
+            convert(ty, hcl::value!({ host = "localhost", port = "8080" })).unwrap(),
+            hcl::value!({ host = "localhost", port = 8080, tls = null })
+        );
+    }
+
+    #[test]
+    fn reports_where_conversion_fails() {
+        let message = |ty: &str, value: hcl::Value| match convert(ty, value) {
+            Err(Error::InvalidVariable { name, message }) if name == "value" => message,
+            other => panic!("expected an invalid variable, got {other:?}"),
+        };
+
+        assert_eq!(message("number", "many".into()), "expected number, got \"many\"");
+        assert_eq!(message("bool", "yes".into()), "expected bool, got \"yes\"");
+        assert_eq!(message("list(number)", hcl::value!([1, "x"])), "element 1: expected number, got \"x\"");
+        assert_eq!(message("tuple([string, number])", hcl::value!(["a"])), "expected tuple([string, number]), got list");
+        assert_eq!(message("object({ host = string })", hcl::value!({ port = 80 })), "attribute `host` is required");
+    }
+
+    #[test]
+    fn rejects_invalid_declarations() {
+        let invalid = |source: &str| matches!(collect(&source.parse().unwrap()), Err(Error::InvalidDeclaration(_)));
+
+        assert!(invalid("variable \"port\" {\n  type = integer\n}\n"));
+        assert!(invalid("variable \"port\" {}\nvariable \"port\" {}\n"));
+        assert!(invalid("variable \"port\" {\n  validation {\n    condition = true\n  }\n}\n"));
+    }
+
+    #[test]
+    fn overrides_are_converted() {
+        let source = "variable \"port\" {\n  type    = number\n  default = 80\n}\n\nvalue = var.port + 1\n";
+
+        assert_eq!(render(source, &[]).unwrap(), hcl::Value::from(81));
+        assert_eq!(render(source, &[("port", "8080".into())]).unwrap(), hcl::Value::from(8081));
+        assert!(matches!(render(source, &[("port", "http".into())]), Err(Error::InvalidVariable { .. })));
+    }
+
+    #[test]
+    fn validations_report_their_message() {
+        let source =
+            "variable \"port\" {\n  type = number\n\n  validation {\n    condition     = var.port > 1024\n    error_message = \"port ${var.port} is privileged\"\n  }\n}\n\nvalue = var.port\n";
+
+        assert_eq!(render(source, &[("port", 8080.into())]).unwrap(), hcl::Value::from(8080));
+        assert!(matches!(render(source, &[("port", 80.into())]), Err(Error::InvalidVariable { message, .. }) if message == "port 80 is privileged"));
+        assert!(matches!(render(source, &[]), Err(Error::InvalidVariable { message, .. }) if message == "no value was given and it has no default"));
+    }
+}
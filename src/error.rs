@@ -0,0 +1,186 @@
+use crate::{converter, schema::Violation};
+
+use hcl::eval::ErrorKind;
+use std::{fmt, io, path::PathBuf};
+
+/// Errors returned while loading, evaluating or rendering a document.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading a source file failed.
+    Io(io::Error),
+    /// The source is not valid HCL, or evaluating it failed.
+    Hcl(hcl::Error),
+    /// The requested file resolves to a path outside of the storage directory.
+    OutsideStorage(PathBuf),
+    /// The document root is not an object.
+    InvalidRoot,
+    /// The document has no `meta` block.
+    MissingMeta,
+    /// A variable block or override tries to replace `const` values.
+    ConstOverride { block: String, keys: Vec<String> },
+    /// The `vars` block redeclares variables from another variable block.
+    ConflictingVars(Vec<String>),
+    /// The output language is not one of `json`, `yaml`/`yml` or `toml`, or `ini` for a kind that renders it.
+    UnknownLanguage,
+    /// Serializing the rendered value failed.
+    Serialize(String),
+    /// A function module named in the settings is not registered.
+    UnknownModule(String),
+    /// A WebAssembly plugin cannot be loaded.
+    Plugin(String),
+    /// A `function` block is malformed.
+    InvalidFunction(String),
+    /// Locals or variables reference each other in a cycle, listed in reference order.
+    CyclicVariables(Vec<String>),
+    /// An `import` block is malformed.
+    InvalidImport(String),
+    /// Loading an imported file failed.
+    Import { name: String, source: Box<Error> },
+    /// Files import each other in a cycle, listed in import order.
+    ImportCycle(Vec<String>),
+    /// `meta.extends` or `meta.lists` is malformed.
+    InvalidExtends(String),
+    /// Files extend each other in a cycle, listed from the rendered file.
+    ExtendsCycle(Vec<String>),
+    /// A `variable` block is malformed.
+    InvalidDeclaration(String),
+    /// A resource of a `kubernetes` document is malformed.
+    InvalidManifest(String),
+    /// A document does not have the shape its `meta.kind` expects.
+    InvalidKind(String),
+    /// A variable has no value, a value of the wrong type, or fails one of its validations.
+    InvalidVariable { name: String, message: String },
+    /// A `dynamic` block is malformed.
+    InvalidDynamic(String),
+    /// A `when` condition is missing or not a bool.
+    InvalidCondition(String),
+    /// `meta.blocks` is malformed, or blocks do not fit the shape it gives them.
+    InvalidBlocks(String),
+    /// An `output` block is malformed.
+    InvalidOutput(String),
+    /// The document has no `output` block with this name.
+    UnknownOutput(String),
+    /// An `assert` block is malformed.
+    InvalidAssertion(String),
+    /// The conditions of `assert` blocks are false, with their names and messages.
+    Assertions(Vec<(String, String)>),
+    /// The schema from `meta.schema` cannot be read or parsed.
+    InvalidSchema(String),
+    /// The rendered value does not match the schema from `meta.schema`.
+    Schema(Vec<Violation>),
+    /// Services of a `docker` document refer to networks, volumes or other entries it does not declare.
+    Compose(Vec<Violation>),
+    /// A sensitive value policy is not `allow`, `redact` or `deny`.
+    UnknownPolicy(String),
+    /// The output contains sensitive values at these JSON pointers while the policy is `deny`.
+    SensitiveOutput(Vec<String>),
+    /// A null policy is not `keep`, `omit`, `error` or `string`.
+    UnknownNullPolicy(String),
+    /// The output contains nulls at these JSON pointers while the null policy is `error`.
+    NullOutput(Vec<String>),
+    /// The output contains integers at these JSON pointers that TOML cannot represent.
+    NumberOutput(Vec<String>),
+    /// An error whose message contained sensitive values, with them redacted.
+    Redacted { status: u16, message: String },
+}
+
+impl Error {
+    /// The HTTP status code that best describes the error.
+    pub fn status(&self) -> u16 {
+        match self {
+            Error::Io(err) if err.kind() == io::ErrorKind::NotFound => 404,
+            Error::OutsideStorage(_) | Error::MissingMeta | Error::UnknownOutput(_) => 404,
+            Error::UnknownLanguage | Error::UnknownPolicy(_) | Error::UnknownNullPolicy(_) => 400,
+            Error::SensitiveOutput(_) => 403,
+            Error::Redacted { status, .. } => *status,
+            Error::Hcl(hcl::Error::Eval(err)) if matches!(err.kind(), ErrorKind::FuncCall(_, message) if converter::exceeded(message)) => 422,
+            Error::InvalidVariable { .. } | Error::Assertions(_) | Error::Schema(_) | Error::Compose(_) | Error::NullOutput(_) | Error::NumberOutput(_) => 422,
+            _ => 500,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{err}"),
+            Error::Hcl(err) => write!(f, "{err}"),
+            Error::OutsideStorage(path) => write!(f, "Path '{}' is outside of storage", path.display()),
+            Error::InvalidRoot => write!(f, "Invalid root object"),
+            Error::MissingMeta => write!(f, "Missing meta object"),
+            Error::ConstOverride { block, keys } => write!(f, "Cannot override const values in '{block}' block for keys: {}", keys.join(", ")),
+            Error::ConflictingVars(keys) => write!(f, "Conflicting variables in 'vars' block for keys: {}", keys.join(", ")),
+            Error::UnknownLanguage => write!(f, "Language not found"),
+            Error::Serialize(err) => write!(f, "{err}"),
+            Error::UnknownModule(name) => write!(f, "Unknown function module '{name}'"),
+            Error::Plugin(err) => write!(f, "{err}"),
+            Error::InvalidFunction(err) => write!(f, "{err}"),
+            Error::CyclicVariables(keys) => write!(f, "Cycle between variables: {}", keys.join(" -> ")),
+            Error::InvalidImport(err) => write!(f, "{err}"),
+            Error::Import { name, source } => write!(f, "Cannot import '{name}': {source}"),
+            Error::ImportCycle(files) => write!(f, "Import cycle: {}", files.join(" -> ")),
+            Error::InvalidExtends(err) => write!(f, "{err}"),
+            Error::ExtendsCycle(files) => write!(f, "Extends cycle: {}", files.join(" -> ")),
+            Error::InvalidDeclaration(err) => write!(f, "{err}"),
+            Error::InvalidManifest(err) => write!(f, "{err}"),
+            Error::InvalidKind(err) => write!(f, "{err}"),
+            Error::InvalidDynamic(err) => write!(f, "{err}"),
+            Error::InvalidCondition(err) => write!(f, "{err}"),
+            Error::InvalidBlocks(err) => write!(f, "{err}"),
+            Error::InvalidOutput(err) => write!(f, "{err}"),
+            Error::UnknownOutput(name) => write!(f, "No output named '{name}'"),
+            Error::InvalidVariable { name, message } => write!(f, "Invalid value for variable '{name}': {message}"),
+            Error::InvalidAssertion(err) => write!(f, "{err}"),
+            Error::Assertions(failed) => {
+                write!(f, "Assertions failed:")?;
+                failed.iter().try_for_each(|(name, message)| write!(f, "\n  {name}: {message}"))
+            }
+            Error::InvalidSchema(err) => write!(f, "{err}"),
+            Error::UnknownPolicy(policy) => write!(f, "Unknown sensitive policy '{policy}', expected allow, redact or deny"),
+            Error::SensitiveOutput(pointers) => write!(f, "Sensitive values cannot be part of the output: {}", pointers.join(", ")),
+            Error::UnknownNullPolicy(policy) => write!(f, "Unknown null policy '{policy}', expected keep, omit, error or string"),
+            Error::NullOutput(pointers) => write!(f, "Null values cannot be part of the output: {}", pointers.join(", ")),
+            Error::NumberOutput(pointers) => write!(f, "Integers above {} cannot be rendered as TOML: {}", i64::MAX, pointers.join(", ")),
+            Error::Redacted { message, .. } => write!(f, "{message}"),
+            Error::Schema(violations) => {
+                write!(f, "Output does not match the schema:")?;
+                violations.iter().try_for_each(|violation| write!(f, "\n  {violation}"))
+            }
+            Error::Compose(violations) => {
+                write!(f, "Compose file refers to undeclared entries:")?;
+                violations.iter().try_for_each(|violation| write!(f, "\n  {violation}"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Hcl(err) => Some(err),
+            Error::Import { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self { Error::Io(err) }
+}
+
+impl From<hcl::Error> for Error {
+    fn from(err: hcl::Error) -> Self { Error::Hcl(err) }
+}
+
+impl From<toml::ser::Error> for Error {
+    fn from(err: toml::ser::Error) -> Self { Error::Serialize(err.to_string()) }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self { Error::Serialize(err.to_string()) }
+}
+
+impl From<serde_yaml_ng::Error> for Error {
+    fn from(err: serde_yaml_ng::Error) -> Self { Error::Serialize(err.to_string()) }
+}
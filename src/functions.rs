@@ -7,26 +7,265 @@ mod global;
 mod hash;
 mod http;
 mod num;
+mod secret;
 mod string;
 
-use hcl::eval::Context;
-use std::{cell::RefCell, rc::Rc};
+use crate::Error;
+
+use hcl::{
+    eval::{Context, FuncArgs, FuncDef, ParamType},
+    expr::FuncName,
+};
+use serde::Serialize;
+use std::{
+    cell::{RefCell, RefMut},
+    collections::HashSet,
+    fmt,
+    rc::Rc,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+/// Error returned by network functions when rendering offline.
+pub const OFFLINE: &str = "network functions are disabled";
+
+static REGISTRY: LazyLock<RwLock<Registry>> = LazyLock::new(|| RwLock::new(Registry::new()));
 
 pub type Functions<'c> = Rc<RefCell<Context<'c>>>;
 
+#[derive(Clone, Serialize)]
+pub struct Param {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub variadic: bool,
+}
+
+/// A group of functions that can be declared into an evaluation context.
+///
+/// Every built-in group (`http`, `string`, ...) is a module, and embedding applications can
+/// [`register`] their own. Functions are usually declared with [`declare_fns!`](crate::declare_fns):
+///
+/// ```
+/// use ship::{declare_fns, functions::{Module, Signature}, hcl::eval::{Context, FuncArgs}};
+/// use std::cell::RefMut;
+///
+/// struct Greet;
+///
+/// fn hello(args: FuncArgs) -> Result<ship::hcl::Value, String> { Ok(format!("hello {}", args[0]).into()) }
+///
+/// impl Module for Greet {
+///     fn name(&self) -> &str { "greet" }
+///
+///     fn declare(&self, mut ctx: RefMut<Context>) -> Vec<Signature> {
+///         declare_fns!(ctx, {
+///             /// Greets someone
+///             hello => greet::hello(name: String)
+///         })
+///     }
+/// }
+///
+/// ship::functions::register(Greet);
+/// ```
+pub trait Module: Send + Sync {
+    /// Name used to enable or disable the module.
+    fn name(&self) -> &str;
+
+    /// Whether the functions reach the network, these are stubbed out when rendering offline.
+    fn network(&self) -> bool { false }
+
+    /// Declares the functions into `ctx` and returns their signatures.
+    fn declare(&self, ctx: RefMut<Context>) -> Vec<Signature>;
+}
+
+struct Builtin {
+    name: &'static str,
+    network: bool,
+    declare: fn(RefMut<Context>) -> Vec<Signature>,
+}
+
+struct Registry {
+    modules: Vec<Arc<dyn Module>>,
+    enabled: Option<HashSet<String>>,
+    disabled: HashSet<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Signature {
+    pub name: String,
+    pub module: String,
+    pub namespace: Option<String>,
+    pub params: Vec<Param>,
+    pub description: String,
+    pub example: Option<&'static str>,
+    #[serde(skip)]
+    func: FuncName,
+}
+
+impl Module for Builtin {
+    fn name(&self) -> &str { self.name }
+
+    fn network(&self) -> bool { self.network }
+
+    fn declare(&self, ctx: RefMut<Context>) -> Vec<Signature> { (self.declare)(ctx) }
+}
+
+impl Registry {
+    fn new() -> Self {
+        let builtin = |name, declare| Arc::new(Builtin { name, declare, network: false }) as Arc<dyn Module>;
+        let network = |name, declare| Arc::new(Builtin { name, declare, network: true }) as Arc<dyn Module>;
+
+        let modules = vec![
+            builtin("cidr", cidr::init),
+            builtin("convert", convert::init),
+            builtin("encoding", crypto::init),
+            builtin("date", date::init),
+            builtin("fs", file::init),
+            builtin("global", global::init),
+            builtin("hash", hash::init),
+            network("http", http::init),
+            builtin("num", num::init),
+            network("secret", secret::init),
+            builtin("string", string::init),
+        ];
+
+        Self {
+            modules,
+            enabled: None,
+            disabled: HashSet::new(),
+        }
+    }
+
+    fn is_enabled(&self, name: &str) -> bool { self.enabled.as_ref().is_none_or(|enabled| enabled.contains(name)) && !self.disabled.contains(name) }
+
+    fn active(&self) -> Vec<Arc<dyn Module>> { self.modules.iter().filter(|module| self.is_enabled(module.name())).cloned().collect() }
+
+    fn check(&self, names: &[String]) -> Result<(), Error> {
+        match names.iter().find(|name| !self.modules.iter().any(|module| module.name() == name.as_str())) {
+            Some(name) => Err(Error::UnknownModule(name.to_owned())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Param {
+    pub fn new(name: &'static str, kind: &'static str, variadic: bool) -> Self {
+        let kind = match kind {
+            "Bool" => "bool",
+            "Number" => "number",
+            "String" => "string",
+            "Array" => "array",
+            "Object" => "object",
+            "Nullable" => "nullable(string)",
+            _ => "any",
+        };
+
+        Self { name, kind, variadic }
+    }
+}
+
+impl Signature {
+    pub fn new(name: FuncName, params: Vec<Param>, description: &str, example: Option<&'static str>) -> Self {
+        let description = description.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+
+        Self {
+            params,
+            example,
+            module: String::new(),
+            name: name.to_string(),
+            description: description.trim().to_string(),
+            namespace: name.namespace.first().map(|ns| ns.to_string()),
+            func: name,
+        }
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|p| match p.variadic {
+                true => format!("..{}: {}", p.name, p.kind),
+                false => format!("{}: {}", p.name, p.kind),
+            })
+            .collect();
+
+        write!(f, "{}({})", self.name, params.join(", "))
+    }
+}
+
+/// Adds a module to every context created afterwards, replacing a module with the same name.
+pub fn register(module: impl Module + 'static) {
+    let mut registry = REGISTRY.write().unwrap();
+
+    registry.modules.retain(|existing| existing.name() != module.name());
+    registry.modules.push(Arc::new(module));
+}
+
+/// Restricts the registered modules to `names`, unless empty.
+pub fn enable(names: &[String]) -> Result<(), Error> {
+    let mut registry = REGISTRY.write().unwrap();
+    registry.check(names)?;
+
+    registry.enabled = match names.is_empty() {
+        true => None,
+        false => Some(names.iter().cloned().collect()),
+    };
+
+    Ok(())
+}
+
+/// Removes the modules in `names` from every context created afterwards.
+pub fn disable(names: &[String]) -> Result<(), Error> {
+    let mut registry = REGISTRY.write().unwrap();
+    registry.check(names)?;
+
+    registry.disabled = names.iter().cloned().collect();
+    Ok(())
+}
+
+/// Names of the registered modules and whether each of them is enabled.
+pub fn modules() -> Vec<(String, bool)> {
+    let registry = REGISTRY.read().unwrap();
+    registry.modules.iter().map(|module| (module.name().to_owned(), registry.is_enabled(module.name()))).collect()
+}
+
 pub fn init<'c>() -> Functions<'c> {
     let ctx = Rc::new(RefCell::new(Context::new()));
+    declare_all(&ctx);
+    ctx
+}
+
+pub fn catalog() -> Vec<Signature> {
+    let ctx = Rc::new(RefCell::new(Context::new()));
+    let mut catalog = declare_all(&ctx);
 
-    cidr::init(ctx.borrow_mut());
-    convert::init(ctx.borrow_mut());
-    crypto::init(ctx.borrow_mut());
-    date::init(ctx.borrow_mut());
-    file::init(ctx.borrow_mut());
-    global::init(ctx.borrow_mut());
-    hash::init(ctx.borrow_mut());
-    http::init(ctx.borrow_mut());
-    num::init(ctx.borrow_mut());
-    string::init(ctx.borrow_mut());
+    catalog.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+    catalog
+}
+
+fn declare_all(ctx: &Functions) -> Vec<Signature> {
+    let mut catalog = Vec::new();
 
-    return ctx;
+    for module in REGISTRY.read().unwrap().active() {
+        catalog.extend(module.declare(ctx.borrow_mut()).into_iter().map(|signature| Signature {
+            module: module.name().to_owned(),
+            ..signature
+        }));
+    }
+
+    catalog
 }
+
+/// Replaces the functions of network modules with stubs failing with [`OFFLINE`].
+pub fn offline(mut ctx: RefMut<Context>) {
+    let scratch = RefCell::new(Context::new());
+
+    for module in REGISTRY.read().unwrap().active().iter().filter(|module| module.network()) {
+        for signature in module.declare(scratch.borrow_mut()) {
+            ctx.declare_func(signature.func, FuncDef::builder().variadic_param(ParamType::Any).build(unavailable));
+        }
+    }
+}
+
+fn unavailable(_args: FuncArgs) -> Result<hcl::Value, String> { Err(OFFLINE.to_string()) }
@@ -1,4 +1,4 @@
-use crate::declare_fns;
+use crate::{declare_fns, functions::Signature};
 
 use hcl::eval::{Context, FuncArgs};
 use std::{cell::RefMut, str::FromStr};
@@ -6,13 +6,24 @@ use std::{cell::RefMut, str::FromStr};
 use ipnetwork::{IpNetwork, Ipv4Network, Ipv6Network};
 use std::net::IpAddr;
 
-pub fn init<'c>(mut ctx: RefMut<Context<'c>>) {
+pub fn init<'c>(mut ctx: RefMut<Context<'c>>) -> Vec<Signature> {
     declare_fns!(ctx, {
-        cidrnetmask => cidr::netmask(String),
-        cidrrange => cidr::range(String),
-        cidrhost => cidr::host(String, Number),
-        cidrsubnets => cidr::subnets(String, Number)
-    });
+        /// Returns the netmask of an IPv4 or IPv6 CIDR prefix
+        #[example = r#"cidr::netmask("172.16.0.0/12")"#]
+        cidrnetmask => cidr::netmask(prefix: String),
+        /// Returns the first and last address of a CIDR prefix
+        #[example = r#"cidr::range("10.0.0.0/24")"#]
+        cidrrange => cidr::range(prefix: String),
+        /// Calculates the address of the given host number within a CIDR prefix
+        #[example = r#"cidr::host("10.12.112.0/20", 16)"#]
+        cidrhost => cidr::host(prefix: String, hostnum: Number),
+        /// Splits a CIDR prefix into every subnet that is `newbits` longer
+        #[example = r#"cidr::subnets("10.1.0.0/16", 2)"#]
+        cidrsubnets => cidr::subnets(prefix: String, newbits: Number),
+        /// Returns whether any two of the CIDR prefixes share addresses
+        #[example = r#"cidr::overlaps(["10.0.0.0/24", "10.0.0.128/25"])"#]
+        cidroverlaps => cidr::overlaps(prefixes: Array)
+    })
 }
 
 fn cidrnetmask(args: FuncArgs) -> Result<hcl::Value, String> {
@@ -103,3 +114,22 @@ fn cidrsubnets(args: FuncArgs) -> Result<hcl::Value, String> {
 
     Ok(hcl::Value::Array(subnets))
 }
+
+fn cidroverlaps(args: FuncArgs) -> Result<hcl::Value, String> {
+    let networks = args[0]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|prefix| {
+            let prefix = prefix.as_str().ok_or("Expected a list of CIDR prefixes")?;
+            IpNetwork::from_str(prefix).map_err(|e| format!("Invalid CIDR prefix: {}", e))
+        })
+        .collect::<Result<Vec<IpNetwork>, String>>()?;
+
+    let overlaps = networks
+        .iter()
+        .enumerate()
+        .any(|(i, a)| networks[i + 1..].iter().any(|b| a.contains(b.network()) || b.contains(a.network())));
+
+    Ok(hcl::Value::Bool(overlaps))
+}
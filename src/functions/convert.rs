@@ -1,17 +1,29 @@
-use crate::declare_fns;
+use crate::{declare_fns, functions::Signature, numbers};
 
 use hcl::eval::{Context, FuncArgs};
 use std::cell::RefMut;
 
-pub fn init<'c>(mut ctx: RefMut<Context<'c>>) {
+pub fn init<'c>(mut ctx: RefMut<Context<'c>>) -> Vec<Signature> {
     declare_fns!(ctx, {
-        tovec => s(..Any),
-        tovec => list(..Any),
-        tovec => tuple(..Any),
-        tostring => string(Any),
-        tonumber => string(Any),
-        toset => set(Array)
-    });
+        /// Collects the arguments into a list
+        #[example = r#"s("a", "b")"#]
+        tovec => s(..values: Any),
+        /// Collects the arguments into a list
+        #[example = r#"list("a", "b")"#]
+        tovec => list(..values: Any),
+        /// Collects the arguments into a list
+        #[example = r#"tuple("a", 1)"#]
+        tovec => tuple(..values: Any),
+        /// Converts a value to its string representation
+        #[example = r#"string(8080)"#]
+        tostring => string(value: Any),
+        /// Parses a value as a number
+        #[example = r#"number("3.14")"#]
+        tonumber => number(value: Any),
+        /// Removes duplicate elements from a list, keeping the first occurrence
+        #[example = r#"set(["a", "b", "a"])"#]
+        toset => set(list: Array)
+    })
 }
 
 fn tovec(args: FuncArgs) -> Result<hcl::Value, String> { Ok(hcl::Value::Array(args.to_vec())) }
@@ -19,11 +31,12 @@ fn tovec(args: FuncArgs) -> Result<hcl::Value, String> { Ok(hcl::Value::Array(ar
 fn tostring(args: FuncArgs) -> Result<hcl::Value, String> { Ok(hcl::Value::String(args[0].to_string())) }
 
 fn tonumber(args: FuncArgs) -> Result<hcl::Value, String> {
-    let value = args[0].to_string();
-    match value.parse::<f64>() {
-        Ok(n) => Ok(hcl::Value::Number(hcl::Number::from_f64(n).unwrap())),
-        Err(e) => Err(format!("Failed to convert to number: {}", e)),
-    }
+    let value = match &args[0] {
+        hcl::Value::String(s) => s.to_owned(),
+        other => other.to_string(),
+    };
+
+    numbers::parse(value.trim()).ok_or_else(|| format!("Failed to convert to number: \"{value}\""))
 }
 
 fn toset(args: FuncArgs) -> Result<hcl::Value, String> {
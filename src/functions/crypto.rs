@@ -1,4 +1,4 @@
-use crate::declare_fns;
+use crate::{declare_fns, functions::Signature, numbers};
 
 use hcl::eval::{Context, FuncArgs};
 use std::cell::RefMut;
@@ -9,17 +9,33 @@ use urlencoding::{decode as url_decode, encode as url_encode};
 use serde_json::{from_str as from_json_str, to_string as to_json_string, Value as JsonValue};
 use serde_yaml_ng::{from_str as from_yaml_str, to_string as to_yaml_string};
 
-pub fn init<'c>(mut ctx: RefMut<Context<'c>>) {
+pub fn init<'c>(mut ctx: RefMut<Context<'c>>) -> Vec<Signature> {
     declare_fns!(ctx, {
-        base64encode => encode::base64(String),
-        base64decode => decode::base64(String),
-        jsonencode => encode::json(Any),
-        jsondecode => decode::json(String),
-        urlencode => encode::url(String),
-        urldecode => decode::url(String),
-        yamlencode => encode::yaml(Any),
-        yamldecode => decode::yaml(String)
-    });
+        /// Encodes a string as base64
+        #[example = r#"encode::base64("hello")"#]
+        base64encode => encode::base64(input: String),
+        /// Decodes a base64 string
+        #[example = r#"decode::base64("aGVsbG8=")"#]
+        base64decode => decode::base64(input: String),
+        /// Encodes a value as a JSON string
+        #[example = r#"encode::json({ port = 8080 })"#]
+        jsonencode => encode::json(value: Any),
+        /// Decodes a JSON string into a value
+        #[example = r#"decode::json("{\"port\": 8080}")"#]
+        jsondecode => decode::json(input: String),
+        /// Percent-encodes a string for use in a URL
+        #[example = r#"encode::url("a b&c")"#]
+        urlencode => encode::url(input: String),
+        /// Decodes a percent-encoded string
+        #[example = r#"decode::url("a%20b%26c")"#]
+        urldecode => decode::url(input: String),
+        /// Encodes a value as a YAML string
+        #[example = r#"encode::yaml({ port = 8080 })"#]
+        yamlencode => encode::yaml(value: Any),
+        /// Decodes a YAML string into a value
+        #[example = r#"decode::yaml("port: 8080")"#]
+        yamldecode => decode::yaml(input: String)
+    })
 }
 
 fn base64encode(args: FuncArgs) -> Result<hcl::Value, String> {
@@ -49,7 +65,7 @@ fn jsonencode(args: FuncArgs) -> Result<hcl::Value, String> {
 fn jsondecode(args: FuncArgs) -> Result<hcl::Value, String> {
     let json_str = args[0].as_str().unwrap();
     match from_json_str(json_str) {
-        Ok(json_value) => Ok(json_to_hcl(json_value)),
+        Ok(json_value) => json_to_hcl(json_value),
         Err(e) => Err(format!("JSON decoding error: {}", e)),
     }
 }
@@ -78,7 +94,7 @@ fn yamlencode(args: FuncArgs) -> Result<hcl::Value, String> {
 fn yamldecode(args: FuncArgs) -> Result<hcl::Value, String> {
     let yaml_str = args[0].as_str().unwrap();
     match from_yaml_str(yaml_str) {
-        Ok(json_value) => Ok(json_to_hcl(json_value)),
+        Ok(json_value) => json_to_hcl(json_value),
         Err(e) => Err(format!("YAML decoding error: {}", e)),
     }
 }
@@ -87,7 +103,7 @@ fn hcl_to_json(value: &hcl::Value) -> JsonValue {
     match value {
         hcl::Value::Null => JsonValue::Null,
         hcl::Value::Bool(b) => JsonValue::Bool(*b),
-        hcl::Value::Number(n) => JsonValue::Number(serde_json::Number::from_f64(n.as_f64().unwrap()).unwrap()),
+        hcl::Value::Number(n) => JsonValue::Number(numbers::to_json(n)),
         hcl::Value::String(s) => JsonValue::String(s.clone()),
         hcl::Value::Array(arr) => JsonValue::Array(arr.iter().map(hcl_to_json).collect()),
         hcl::Value::Object(map) => {
@@ -100,19 +116,19 @@ fn hcl_to_json(value: &hcl::Value) -> JsonValue {
     }
 }
 
-fn json_to_hcl(value: JsonValue) -> hcl::Value {
+fn json_to_hcl(value: JsonValue) -> Result<hcl::Value, String> {
     match value {
-        JsonValue::Null => hcl::Value::Null,
-        JsonValue::Bool(b) => hcl::Value::Bool(b),
-        JsonValue::Number(n) => hcl::Value::Number(hcl::Number::from_f64(n.as_f64().unwrap()).unwrap()),
-        JsonValue::String(s) => hcl::Value::String(s),
-        JsonValue::Array(arr) => hcl::Value::Array(arr.into_iter().map(json_to_hcl).collect()),
+        JsonValue::Null => Ok(hcl::Value::Null),
+        JsonValue::Bool(b) => Ok(hcl::Value::Bool(b)),
+        JsonValue::Number(n) => numbers::from_json(&n),
+        JsonValue::String(s) => Ok(hcl::Value::String(s)),
+        JsonValue::Array(arr) => Ok(hcl::Value::Array(arr.into_iter().map(json_to_hcl).collect::<Result<_, _>>()?)),
         JsonValue::Object(map) => {
             let mut hcl_map = hcl::Map::new();
             for (k, v) in map {
-                hcl_map.insert(k, json_to_hcl(v));
+                hcl_map.insert(k, json_to_hcl(v)?);
             }
-            hcl::Value::Object(hcl_map)
+            Ok(hcl::Value::Object(hcl_map))
         }
     }
 }
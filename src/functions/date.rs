@@ -1,25 +1,33 @@
-use crate::declare_fns;
+use crate::{declare_fns, functions::Signature};
 
 use chrono::{Duration, TimeZone, Utc};
 use hcl::eval::{Context, FuncArgs};
 use std::cell::RefMut;
 
-pub fn init<'c>(mut ctx: RefMut<Context<'c>>) {
+pub fn init<'c>(mut ctx: RefMut<Context<'c>>) -> Vec<Signature> {
     declare_fns!(ctx, {
+        /// Returns the current Unix timestamp in seconds
+        #[example = r#"date::timestamp()"#]
         timestamp => date::timestamp(),
-        timeadd => date::timeadd(Number, String),
-        parseduration => date::duration(String),
-        formatdate => date::format(String, Number)
-    });
+        /// Adds a duration such as "1h30m" to a Unix timestamp
+        #[example = r#"date::timeadd(1700000000, "1h30m")"#]
+        timeadd => date::timeadd(timestamp: Number, duration: String),
+        /// Converts a duration such as "1d12h" to seconds
+        #[example = r#"date::duration("1d12h")"#]
+        parseduration => date::duration(duration: String),
+        /// Formats a Unix timestamp with a strftime format string
+        #[example = r#"date::format("%Y-%m-%d", 1700000000)"#]
+        formatdate => date::format(format: String, timestamp: Number)
+    })
 }
 
 fn timestamp(_args: FuncArgs) -> Result<hcl::Value, String> {
     let now = Utc::now().timestamp();
-    Ok(hcl::Value::Number(hcl::Number::from_f64(now as f64).unwrap()))
+    Ok(hcl::Value::Number(now.into()))
 }
 
 fn timeadd(args: FuncArgs) -> Result<hcl::Value, String> {
-    let timestamp = args[0].as_number().unwrap().as_f64().unwrap() as i64;
+    let timestamp = timestamp_of(&args[0])?;
     let duration_str = args[1].as_str().unwrap();
 
     let duration = match parse_duration(duration_str) {
@@ -29,7 +37,7 @@ fn timeadd(args: FuncArgs) -> Result<hcl::Value, String> {
 
     let datetime = Utc.timestamp_opt(timestamp, 0).unwrap().checked_add_signed(duration).ok_or("Time overflow")?;
 
-    Ok(hcl::Value::Number(hcl::Number::from_f64(datetime.timestamp() as f64).unwrap()))
+    Ok(hcl::Value::Number(datetime.timestamp().into()))
 }
 
 fn parseduration(args: FuncArgs) -> Result<hcl::Value, String> {
@@ -40,24 +48,33 @@ fn parseduration(args: FuncArgs) -> Result<hcl::Value, String> {
         Err(e) => return Err(format!("Invalid duration: {}", e)),
     };
 
-    Ok(hcl::Value::Number(hcl::Number::from_f64(duration.num_seconds() as f64).unwrap()))
+    Ok(hcl::Value::Number(duration.num_seconds().into()))
 }
 
 fn formatdate(args: FuncArgs) -> Result<hcl::Value, String> {
     let format = args[0].as_str().unwrap();
-    let timestamp = args[1].as_number().unwrap().as_f64().unwrap() as i64;
+    let timestamp = timestamp_of(&args[1])?;
 
     let datetime = Utc.timestamp_opt(timestamp, 0).unwrap();
     Ok(hcl::Value::String(datetime.format(format).to_string()))
 }
 
+/// A timestamp argument, in whole seconds.
+fn timestamp_of(value: &hcl::Value) -> Result<i64, String> {
+    let number = value.as_number().ok_or("Timestamp must be a number")?;
+    number
+        .as_i64()
+        .or_else(|| number.as_f64().filter(|float| float.is_finite()).map(|float| float as i64))
+        .ok_or_else(|| format!("Timestamp out of range: {number}"))
+}
+
 fn parse_duration(duration_str: &str) -> Result<Duration, String> {
     let mut chars = duration_str.chars().peekable();
     let mut value = String::new();
     let mut total = Duration::zero();
 
     while let Some(&ch) = chars.peek() {
-        if ch.is_digit(10) {
+        if ch.is_ascii_digit() {
             value.push(ch);
             chars.next();
         } else {
@@ -65,10 +82,10 @@ fn parse_duration(duration_str: &str) -> Result<Duration, String> {
             value.clear();
 
             match chars.next() {
-                Some('s') => total = total + Duration::seconds(num),
-                Some('m') => total = total + Duration::minutes(num),
-                Some('h') => total = total + Duration::hours(num),
-                Some('d') => total = total + Duration::days(num),
+                Some('s') => total += Duration::seconds(num),
+                Some('m') => total += Duration::minutes(num),
+                Some('h') => total += Duration::hours(num),
+                Some('d') => total += Duration::days(num),
                 Some(unit) => return Err(format!("Invalid duration unit: {}", unit)),
                 None => return Err("Duration string ended unexpectedly".to_string()),
             }
@@ -1,4 +1,4 @@
-use crate::declare_fns;
+use crate::{declare_fns, functions::Signature};
 use hcl::eval::{Context, FuncArgs};
 
 use std::cell::RefMut;
@@ -9,14 +9,24 @@ use md5::{Digest, Md5};
 use sha1::Sha1;
 use sha2::{Sha256, Sha512};
 
-pub fn init<'c>(mut ctx: RefMut<Context<'c>>) {
+pub fn init<'c>(mut ctx: RefMut<Context<'c>>) -> Vec<Signature> {
     declare_fns!(ctx, {
-        file => fs::read(String),
-        filemd5 => fs::md5(String),
-        filesha1 => fs::sha1(String),
-        filesha256 => fs::sha256(String),
-        filesha512 => fs::sha512(String)
-    });
+        /// Reads the contents of a file as a string
+        #[example = r#"fs::read("motd.txt")"#]
+        file => fs::read(path: String),
+        /// Returns the hex MD5 digest of a file's contents
+        #[example = r#"fs::md5("motd.txt")"#]
+        filemd5 => fs::md5(path: String),
+        /// Returns the hex SHA-1 digest of a file's contents
+        #[example = r#"fs::sha1("motd.txt")"#]
+        filesha1 => fs::sha1(path: String),
+        /// Returns the hex SHA-256 digest of a file's contents
+        #[example = r#"fs::sha256("motd.txt")"#]
+        filesha256 => fs::sha256(path: String),
+        /// Returns the hex SHA-512 digest of a file's contents
+        #[example = r#"fs::sha512("motd.txt")"#]
+        filesha512 => fs::sha512(path: String)
+    })
 }
 
 fn file(args: FuncArgs) -> Result<hcl::Value, String> {
@@ -1,24 +1,53 @@
-use crate::declare_fns;
+use crate::{declare_fns, functions::Signature, sensitive};
 
 use hcl::eval::{Context, FuncArgs};
 use std::cell::RefMut;
 
-pub fn init<'c>(mut ctx: RefMut<Context<'c>>) {
+pub fn init<'c>(mut ctx: RefMut<Context<'c>>) -> Vec<Signature> {
     declare_fns!(ctx, {
-        join => join(Array, String),
-        split => split(String, String),
-        range => range(Number, Number),
-        merge => merge(..Object),
-        format => format(..Any),
-        concat => concat(..String),
-        length => length(Any),
-        unique => unique(Array),
-        compact => compact(Object),
-        type_of => type_of(Any),
-        reverse => reverse(Any),
-        flatten => flatten(Array),
-        contains => contains(Any, Any)
-    });
+        /// Joins the elements of a list into a string
+        #[example = r#"join(["a", "b"], ",")"#]
+        join => join(list: Array, separator: String),
+        /// Splits a string into a list on a separator
+        #[example = r#"split("a,b", ",")"#]
+        split => split(input: String, separator: String),
+        /// Returns the integers from start up to, but excluding, end
+        #[example = r#"range(0, 3)"#]
+        range => range(start: Number, end: Number),
+        /// Merges maps, keys in later maps override earlier ones
+        #[example = r#"merge({ a = 1 }, { b = 2 })"#]
+        merge => merge(..maps: Object),
+        /// Formats a string using %s, %d and %f placeholders
+        #[example = r#"format("%s:%d", "localhost", 8080)"#]
+        format => format(..args: Any),
+        /// Concatenates strings
+        #[example = r#"concat("foo", "bar")"#]
+        concat => concat(..strings: String),
+        /// Returns the number of elements in a list or map, or bytes in a string
+        #[example = r#"length(["a", "b"])"#]
+        length => length(value: Any),
+        /// Removes duplicate elements from a list
+        #[example = r#"unique([1, 2, 1])"#]
+        unique => unique(list: Array),
+        /// Removes null elements from a list, or null values from a map
+        #[example = r#"compact(["a", null])"#]
+        compact => compact(collection: Any),
+        /// Returns the type name of a value
+        #[example = r#"type_of(8080)"#]
+        type_of => type_of(value: Any),
+        /// Reverses a list or string
+        #[example = r#"reverse([1, 2, 3])"#]
+        reverse => reverse(value: Any),
+        /// Flattens nested lists into a single list
+        #[example = r#"flatten([[1], [2, [3]]])"#]
+        flatten => flatten(list: Array),
+        /// Checks whether a list contains an element or a string contains a substring
+        #[example = r#"contains(["a", "b"], "a")"#]
+        contains => contains(haystack: Any, needle: Any),
+        /// Marks the strings in a value as sensitive, redacting them from errors and subjecting them to the output policy
+        #[example = r#"sensitive("hunter2")"#]
+        sensitive => sensitive(value: Any)
+    })
 }
 
 fn join(args: FuncArgs) -> Result<hcl::Value, String> {
@@ -139,11 +168,10 @@ fn length(args: FuncArgs) -> Result<hcl::Value, String> {
 }
 
 fn compact(args: FuncArgs) -> Result<hcl::Value, String> {
-    if let hcl::Value::Array(arr) = &args[0] {
-        let filtered: Vec<hcl::Value> = arr.iter().filter(|v| !matches!(v, hcl::Value::Null)).cloned().collect();
-        Ok(hcl::Value::Array(filtered))
-    } else {
-        Err("compact() requires array argument".to_string())
+    match &args[0] {
+        hcl::Value::Array(arr) => Ok(hcl::Value::Array(arr.iter().filter(|v| !v.is_null()).cloned().collect())),
+        hcl::Value::Object(map) => Ok(hcl::Value::Object(map.iter().filter(|(_, v)| !v.is_null()).map(|(k, v)| (k.to_owned(), v.to_owned())).collect())),
+        _ => Err("compact() requires an array or object argument".to_string()),
     }
 }
 
@@ -201,3 +229,8 @@ fn flatten(args: FuncArgs) -> Result<hcl::Value, String> {
         Err("flatten() requires array argument".to_string())
     }
 }
+
+fn sensitive(args: FuncArgs) -> Result<hcl::Value, String> {
+    sensitive::mark(&args[0]);
+    Ok(args[0].to_owned())
+}
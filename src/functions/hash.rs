@@ -1,4 +1,4 @@
-use crate::declare_fns;
+use crate::{declare_fns, functions::Signature};
 
 use hcl::eval::{Context, FuncArgs};
 use std::cell::RefMut;
@@ -9,16 +9,30 @@ use sha1::Sha1;
 use sha2::{Sha256, Sha512};
 use uuid::Uuid;
 
-pub fn init<'c>(mut ctx: RefMut<Context<'c>>) {
+pub fn init<'c>(mut ctx: RefMut<Context<'c>>) -> Vec<Signature> {
     declare_fns!(ctx, {
+        /// Generates a random version 4 UUID
+        #[example = r#"uuid()"#]
         uuid_gen => uuid(),
-        uuidv5 => uuidv5(String, String),
-        bcrypt_hash => hash::bcrypt(String),
-        md5_hash => hash::md5(String),
-        sha1_hash => hash::sha1(String),
-        sha256_hash => hash::sha256(String),
-        sha512_hash => hash::sha512(String)
-    });
+        /// Generates a name-based version 5 UUID within a namespace UUID
+        #[example = r#"uuidv5("6ba7b810-9dad-11d1-80b4-00c04fd430c8", "example.com")"#]
+        uuidv5 => uuidv5(namespace: String, name: String),
+        /// Hashes a string with bcrypt using the default cost
+        #[example = r#"hash::bcrypt("hunter2")"#]
+        bcrypt_hash => hash::bcrypt(input: String),
+        /// Returns the hex MD5 digest of a string
+        #[example = r#"hash::md5("hello")"#]
+        md5_hash => hash::md5(input: String),
+        /// Returns the hex SHA-1 digest of a string
+        #[example = r#"hash::sha1("hello")"#]
+        sha1_hash => hash::sha1(input: String),
+        /// Returns the hex SHA-256 digest of a string
+        #[example = r#"hash::sha256("hello")"#]
+        sha256_hash => hash::sha256(input: String),
+        /// Returns the hex SHA-512 digest of a string
+        #[example = r#"hash::sha512("hello")"#]
+        sha512_hash => hash::sha512(input: String)
+    })
 }
 
 fn uuid_gen(_args: FuncArgs) -> Result<hcl::Value, String> { Ok(hcl::Value::String(Uuid::new_v4().to_string())) }
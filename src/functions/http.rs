@@ -1,17 +1,24 @@
-use crate::declare_fns;
+use crate::{declare_fns, functions::Signature};
 
 use hcl::eval::{Context, FuncArgs};
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use std::cell::RefMut;
 
-pub fn init<'c>(mut ctx: RefMut<Context<'c>>) {
+pub fn init<'c>(mut ctx: RefMut<Context<'c>>) -> Vec<Signature> {
     declare_fns!(ctx, {
-        vault_kv => secret::kv(String, ..Nullable),
-        http_get => http::get(String, ..Any),
-        http_post => http::post(String, String, ..Any),
-        http_json => http::post_json(String, Any, ..Any),
-        http_put => http::put(String, String, ..Any)
-    });
+        /// Sends a GET request and returns the response body
+        #[example = r#"http::get("https://example.com/health", { Accept = "text/plain" })"#]
+        http_get => http::get(url: String, ..headers: Any),
+        /// Sends a POST request and returns the response body
+        #[example = r#"http::post("https://example.com/hook", "ping")"#]
+        http_post => http::post(url: String, body: String, ..headers: Any),
+        /// Sends a value as a JSON POST request and returns the response body
+        #[example = r#"http::post_json("https://example.com/api", { name = "web" })"#]
+        http_json => http::post_json(url: String, body: Any, ..headers: Any),
+        /// Sends a PUT request and returns the response body
+        #[example = r#"http::put("https://example.com/item/1", "value")"#]
+        http_put => http::put(url: String, body: String, ..headers: Any)
+    })
 }
 
 fn parse_headers(headers_arg: &Option<&hcl::Value>) -> Option<HeaderMap> {
@@ -35,71 +42,6 @@ fn parse_headers(headers_arg: &Option<&hcl::Value>) -> Option<HeaderMap> {
     }
 }
 
-fn vault_kv(args: FuncArgs) -> Result<hcl::Value, String> {
-    let config = crate::config::read();
-    let value = args[0].as_str().unwrap();
-
-    let vault = match config.settings.vault {
-        Some(vault) => vault,
-        None => return Err("Vault not configured in server settings".into()),
-    };
-
-    if args.len() > 2 {
-        return Err("Too many arguments, expected at most 2".into());
-    }
-
-    let mut key = None;
-
-    if args.len() > 1 && args[1] != hcl::Value::Null {
-        key = Some(args[1].to_owned());
-    }
-
-    let client = reqwest::blocking::Client::new();
-    let request = client.get(format!("{}/v1/kv/data/{value}", vault.url)).header("X-Vault-Token", vault.token);
-
-    match request.send() {
-        Ok(response) => match response.json::<hcl::Object<String, hcl::Value>>() {
-            Ok(json) => match json.get("data") {
-                Some(data) => {
-                    let values = match data.as_object() {
-                        Some(values) => values.get("data"),
-                        None => return Ok(data.to_owned()),
-                    };
-
-                    let secret_map = match values {
-                        Some(secret) => secret.as_object(),
-                        None => return Ok(data.to_owned()),
-                    };
-
-                    let key_value = match key {
-                        Some(key) => key,
-                        None => return Ok(hcl::Value::Object(secret_map.expect("Expected valid early returns").to_owned())),
-                    };
-
-                    let key = match key_value.as_str() {
-                        Some(key) => key,
-                        None => return Ok(hcl::Value::Object(secret_map.expect("Expected valid early returns").to_owned())),
-                    };
-
-                    let secret = match secret_map {
-                        Some(secret) => secret.get(key),
-                        None => return Ok(hcl::Value::Object(secret_map.expect("Expected valid early returns").to_owned())),
-                    };
-
-                    if let Some(val) = secret {
-                        return Ok(val.to_owned());
-                    }
-
-                    Ok(data.to_owned())
-                }
-                None => Err("Unable to decode json".to_string()),
-            },
-            Err(e) => Err(format!("Failed to read response: {}", e)),
-        },
-        Err(e) => Err(format!("HTTP GET request failed: {}", e)),
-    }
-}
-
 fn http_get(args: FuncArgs) -> Result<hcl::Value, String> {
     let url = args[0].as_str().unwrap();
     let headers = parse_headers(&args.get(1));
@@ -1,24 +1,46 @@
-use crate::declare_fns;
+use crate::{declare_fns, functions::Signature, numbers};
 
 use hcl::eval::{Context, FuncArgs};
-use std::{cell::RefMut, str::FromStr};
+use std::{cell::RefMut, cmp::Ordering};
 
-pub fn init<'c>(mut ctx: RefMut<Context<'c>>) {
+pub fn init<'c>(mut ctx: RefMut<Context<'c>>) -> Vec<Signature> {
     declare_fns!(ctx, {
-        abs => abs(Number),
-        ceil => ceil(Number),
-        floor => floor(Number),
-        max => max(Array),
-        min => min(Array),
-        sum => sum(Array),
-        parseint => parseint(String)
-    });
+        /// Returns the absolute value of a number
+        #[example = r#"abs(-4)"#]
+        abs => abs(value: Number),
+        /// Rounds a number up to the nearest integer
+        #[example = r#"ceil(4.2)"#]
+        ceil => ceil(value: Number),
+        /// Rounds a number down to the nearest integer
+        #[example = r#"floor(4.8)"#]
+        floor => floor(value: Number),
+        /// Returns the largest number in a list
+        #[example = r#"max([1, 5, 3])"#]
+        max => max(list: Array),
+        /// Returns the smallest number in a list
+        #[example = r#"min([1, 5, 3])"#]
+        min => min(list: Array),
+        /// Returns the sum of the numbers in a list
+        #[example = r#"sum([1, 2, 3])"#]
+        sum => sum(list: Array),
+        /// Parses a string as a base 10 integer
+        #[example = r#"parseint("42")"#]
+        parseint => parseint(input: String)
+    })
 }
 
 fn sum(args: FuncArgs) -> Result<hcl::Value, String> {
     if let hcl::Value::Array(arr) = &args[0] {
-        let sum = arr.iter().filter_map(|v| v.as_number()).fold(0.0, |acc, x| acc + x.as_f64().unwrap());
-        Ok(hcl::Value::Number(hcl::Number::from_f64(sum).unwrap()))
+        let nums: Vec<&hcl::Number> = arr.iter().filter_map(|v| v.as_number()).collect();
+
+        // integers are summed exactly, and only fall back to floats with a fraction or on overflow
+        let exact = nums.iter().try_fold(0i128, |acc, n| acc.checked_add(numbers::as_integer(n)?));
+        let sum = match exact {
+            Some(sum) => numbers::from_integer(sum),
+            None => numbers::from_f64(nums.iter().fold(0.0, |acc, n| acc + n.as_f64().unwrap())),
+        };
+
+        sum.map(hcl::Value::Number).ok_or_else(|| "sum() does not fit into a 64-bit number".to_string())
     } else {
         Err("sum() requires array argument".to_string())
     }
@@ -28,9 +50,8 @@ fn max(args: FuncArgs) -> Result<hcl::Value, String> {
     if let hcl::Value::Array(arr) = &args[0] {
         arr.iter()
             .filter_map(|v| v.as_number())
-            .map(|n| n.as_f64().unwrap())
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .map(|n| hcl::Value::Number(hcl::Number::from_f64(n).unwrap()))
+            .max_by(|a, b| compare(a, b))
+            .map(|n| hcl::Value::Number(n.to_owned()))
             .ok_or_else(|| "max() requires non-empty array of numbers".to_string())
     } else {
         Err("max() requires array argument".to_string())
@@ -41,9 +62,8 @@ fn min(args: FuncArgs) -> Result<hcl::Value, String> {
     if let hcl::Value::Array(arr) = &args[0] {
         arr.iter()
             .filter_map(|v| v.as_number())
-            .map(|n| n.as_f64().unwrap())
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-            .map(|n| hcl::Value::Number(hcl::Number::from_f64(n).unwrap()))
+            .min_by(|a, b| compare(a, b))
+            .map(|n| hcl::Value::Number(n.to_owned()))
             .ok_or_else(|| "min() requires non-empty array of numbers".to_string())
     } else {
         Err("min() requires array argument".to_string())
@@ -52,7 +72,12 @@ fn min(args: FuncArgs) -> Result<hcl::Value, String> {
 
 fn abs(args: FuncArgs) -> Result<hcl::Value, String> {
     if let Some(num) = args[0].as_number() {
-        Ok(hcl::Value::Number(hcl::Number::from_f64(num.as_f64().unwrap().abs()).unwrap()))
+        let abs = match numbers::as_integer(num) {
+            Some(int) => numbers::from_integer(int.abs()),
+            None => numbers::from_f64(num.as_f64().unwrap().abs()),
+        };
+
+        abs.map(hcl::Value::Number).ok_or_else(|| "abs() does not fit into a 64-bit number".to_string())
     } else {
         Err("abs() requires number argument".to_string())
     }
@@ -60,7 +85,7 @@ fn abs(args: FuncArgs) -> Result<hcl::Value, String> {
 
 fn ceil(args: FuncArgs) -> Result<hcl::Value, String> {
     if let Some(num) = args[0].as_number() {
-        Ok(hcl::Value::Number(hcl::Number::from_f64(num.as_f64().unwrap().ceil()).unwrap()))
+        Ok(hcl::Value::Number(round(num, f64::ceil)))
     } else {
         Err("ceil() requires number argument".to_string())
     }
@@ -68,7 +93,7 @@ fn ceil(args: FuncArgs) -> Result<hcl::Value, String> {
 
 fn floor(args: FuncArgs) -> Result<hcl::Value, String> {
     if let Some(num) = args[0].as_number() {
-        Ok(hcl::Value::Number(hcl::Number::from_f64(num.as_f64().unwrap().floor()).unwrap()))
+        Ok(hcl::Value::Number(round(num, f64::floor)))
     } else {
         Err("floor() requires number argument".to_string())
     }
@@ -76,9 +101,20 @@ fn floor(args: FuncArgs) -> Result<hcl::Value, String> {
 
 fn parseint(args: FuncArgs) -> Result<hcl::Value, String> {
     let value = args[0].as_str().unwrap();
+    numbers::parse_integer(value).ok_or_else(|| format!("Failed to parse integer: \"{value}\" is not a 64-bit integer"))
+}
+
+/// Integers are already rounded, and keep all their digits.
+fn round(num: &hcl::Number, op: fn(f64) -> f64) -> hcl::Number {
+    match num.is_f64() {
+        true => numbers::from_f64(op(num.as_f64().unwrap())).unwrap_or_else(|| num.to_owned()),
+        false => num.to_owned(),
+    }
+}
 
-    match i64::from_str(value) {
-        Ok(n) => Ok(hcl::Value::Number(hcl::Number::from_f64(n as f64).unwrap())),
-        Err(e) => Err(format!("Failed to parse integer: {}", e)),
+fn compare(a: &hcl::Number, b: &hcl::Number) -> Ordering {
+    match (numbers::as_integer(a), numbers::as_integer(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.as_f64().unwrap().partial_cmp(&b.as_f64().unwrap()).unwrap_or(Ordering::Equal),
     }
 }
@@ -0,0 +1,34 @@
+use crate::{declare_fns, functions::Signature, secrets, sensitive};
+
+use hcl::eval::{Context, FuncArgs};
+use std::cell::RefMut;
+
+pub fn init<'c>(mut ctx: RefMut<Context<'c>>) -> Vec<Signature> {
+    declare_fns!(ctx, {
+        /// Reads a secret from the Vault KV store, or a single key of it
+        #[example = r#"secret::kv("app/db", "password")"#]
+        vault_kv => secret::kv(path: String, ..key: Nullable)
+    })
+}
+
+fn vault_kv(args: FuncArgs) -> Result<hcl::Value, String> {
+    let path = args[0].as_str().unwrap();
+    let provider = secrets::current().ok_or("Vault not configured in server settings")?;
+
+    if args.len() > 2 {
+        return Err("Too many arguments, expected at most 2".into());
+    }
+
+    let secret = provider.kv(path)?;
+
+    let value = match args.get(1).and_then(hcl::Value::as_str) {
+        Some(key) => match secret.as_object().and_then(|values| values.get(key)) {
+            Some(value) => value.to_owned(),
+            None => secret,
+        },
+        None => secret,
+    };
+
+    sensitive::mark(&value);
+    Ok(value)
+}
@@ -1,19 +1,35 @@
-use crate::declare_fns;
+use crate::{declare_fns, functions::Signature};
 
 use hcl::eval::{Context, FuncArgs};
 use std::cell::RefMut;
 
-pub fn init<'c>(mut ctx: RefMut<Context<'c>>) {
+pub fn init<'c>(mut ctx: RefMut<Context<'c>>) -> Vec<Signature> {
     declare_fns!(ctx, {
-        keys => map::keys(Object),
-        values => map::values(Object),
-        upper => str::upper(String),
-        lower => str::lower(String),
-        trim => str::trim(String, String),
-        trimspace => str::trimspace(String),
-        trimprefix => str::trimprefix(String, String),
-        trimsuffix => str::trimsuffix(String, String)
-    });
+        /// Returns the keys of a map
+        #[example = r#"map::keys({ a = 1, b = 2 })"#]
+        keys => map::keys(map: Object),
+        /// Returns the values of a map
+        #[example = r#"map::values({ a = 1, b = 2 })"#]
+        values => map::values(map: Object),
+        /// Converts a string to upper case
+        #[example = r#"str::upper("web")"#]
+        upper => str::upper(input: String),
+        /// Converts a string to lower case
+        #[example = r#"str::lower("WEB")"#]
+        lower => str::lower(input: String),
+        /// Removes any of the characters in cutset from both ends of a string
+        #[example = r#"str::trim("--web--", "-")"#]
+        trim => str::trim(input: String, cutset: String),
+        /// Removes whitespace from both ends of a string
+        #[example = r#"str::trimspace("  web  ")"#]
+        trimspace => str::trimspace(input: String),
+        /// Removes a prefix from a string if present
+        #[example = r#"str::trimprefix("v1.2.0", "v")"#]
+        trimprefix => str::trimprefix(input: String, prefix: String),
+        /// Removes a suffix from a string if present
+        #[example = r#"str::trimsuffix("web.local", ".local")"#]
+        trimsuffix => str::trimsuffix(input: String, suffix: String)
+    })
 }
 
 fn keys(args: FuncArgs) -> Result<hcl::Value, String> {
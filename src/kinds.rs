@@ -0,0 +1,72 @@
+//! Processors for the `meta.kind` of a document.
+//!
+//! A kind gives documents of one sort of configuration file their shape: it can declare variables
+//! before the document is evaluated, check and transform the evaluated value, and choose how it is
+//! rendered. The `docker`, `kubernetes` and `systemd` kinds are built in, and embedding applications
+//! can [`register`] their own.
+
+mod docker;
+mod kubernetes;
+mod systemd;
+
+use crate::{Error, Language};
+
+use std::sync::{Arc, LazyLock, RwLock};
+
+static REGISTRY: LazyLock<RwLock<Vec<Arc<dyn Kind>>>> = LazyLock::new(|| RwLock::new(vec![Arc::new(docker::Docker), Arc::new(kubernetes::Kubernetes), Arc::new(systemd::Systemd)]));
+
+/// A processor for documents whose `meta.kind` is its [`name`](Kind::name).
+///
+/// Every step has a default that leaves the document as it is, so a kind only implements what it needs:
+///
+/// ```
+/// use ship::{hcl, kinds::Kind, Error};
+///
+/// struct Nginx;
+///
+/// impl Kind for Nginx {
+///     fn name(&self) -> &str { "nginx" }
+///
+///     fn validate(&self, value: &hcl::Value) -> Result<(), Error> {
+///         match value.as_object().is_some_and(|obj| obj.contains_key("server")) {
+///             true => Ok(()),
+///             false => Err(Error::InvalidKind("nginx documents need a `server` block".into())),
+///         }
+///     }
+/// }
+///
+/// ship::kinds::register(Nginx);
+/// ```
+pub trait Kind: Send + Sync {
+    /// The `meta.kind` this processor handles.
+    fn name(&self) -> &str;
+
+    /// Variables declared before the document is evaluated, given its top-level values once `dynamic` blocks are
+    /// expanded and `when` conditions applied. Attributes are not evaluated yet and hold their expressions.
+    fn declare(&self, _document: &hcl::Map<String, hcl::Value>) -> Vec<(String, hcl::Value)> { Vec::new() }
+
+    /// Checks the evaluated document, before the sensitive value policy is applied.
+    fn validate(&self, _value: &hcl::Value) -> Result<(), Error> { Ok(()) }
+
+    /// Turns the evaluated document into the value that is rendered, given the evaluated `meta` block.
+    fn transform(&self, value: hcl::Value, _meta: &hcl::Map<String, hcl::Value>) -> Result<hcl::Value, Error> { Ok(value) }
+
+    /// Format used when neither the request nor `meta.file` names one.
+    fn format(&self) -> Option<Language> { None }
+
+    /// Splits the transformed value into the documents of a YAML stream, instead of rendering it as one.
+    fn stream(&self, _value: &hcl::Value) -> Option<Vec<hcl::Value>> { None }
+
+    /// Renders the transformed value in a format only the kind knows, such as systemd unit files.
+    fn render(&self, _value: &hcl::Value, _lang: &Language) -> Option<Result<String, Error>> { None }
+}
+
+/// Adds a kind for every document loaded afterwards, replacing a kind with the same name.
+pub fn register(kind: impl Kind + 'static) {
+    let mut registry = REGISTRY.write().unwrap();
+
+    registry.retain(|existing| existing.name() != kind.name());
+    registry.push(Arc::new(kind));
+}
+
+pub(crate) fn get(name: &str) -> Option<Arc<dyn Kind>> { REGISTRY.read().unwrap().iter().find(|kind| kind.name() == name).cloned() }
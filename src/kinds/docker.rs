@@ -0,0 +1,222 @@
+use super::Kind;
+use crate::{schema::Violation, Error, Language};
+
+/// The top-level sections of a compose file, in the order of the compose specification.
+const ORDER: &[&str] = &["version", "name", "include", "services", "networks", "volumes", "configs", "secrets"];
+
+/// The sections whose entry names are declared under `compose`, e.g. `compose.services` as the list of service names.
+const SECTIONS: &[&str] = &["services", "networks", "volumes", "configs", "secrets"];
+
+/// Compose files, `meta.kind = "docker"`.
+pub(super) struct Docker;
+
+impl Kind for Docker {
+    fn name(&self) -> &str { "docker" }
+
+    fn declare(&self, document: &hcl::Map<String, hcl::Value>) -> Vec<(String, hcl::Value)> {
+        let names = |section: &str| {
+            document
+                .get(section)
+                .and_then(hcl::Value::as_object)
+                .map(|entries| entries.keys().cloned().map(hcl::Value::from).collect())
+                .unwrap_or_default()
+        };
+        let sections = SECTIONS.iter().map(|section| (section.to_string(), hcl::Value::Array(names(section))));
+        vec![("compose".to_owned(), hcl::Value::Object(sections.collect()))]
+    }
+
+    fn validate(&self, value: &hcl::Value) -> Result<(), Error> { check(value) }
+
+    fn transform(&self, value: hcl::Value, _meta: &hcl::Map<String, hcl::Value>) -> Result<hcl::Value, Error> { Ok(order(value)) }
+
+    fn format(&self) -> Option<Language> { Some(Language::YAML) }
+}
+
+/// Checks that the `depends_on`, `networks`, `volumes`, `configs` and `secrets` of every service refer to
+/// entries the document declares.
+fn check(value: &hcl::Value) -> Result<(), Error> {
+    let Some(obj) = value.as_object() else { return Ok(()) };
+    let Some(services) = obj.get("services").and_then(hcl::Value::as_object) else { return Ok(()) };
+
+    let declared = |section: &str| -> Vec<String> { obj.get(section).and_then(hcl::Value::as_object).map(|entries| entries.keys().cloned().collect()).unwrap_or_default() };
+    let (services_declared, volumes, configs, secrets) = (declared("services"), declared("volumes"), declared("configs"), declared("secrets"));
+
+    // every project has a `default` network, whether it is declared or not
+    let mut networks = declared("networks");
+    networks.push("default".into());
+
+    let mut violations = Vec::new();
+
+    for (name, service) in services {
+        let Some(service) = service.as_object() else { continue };
+        let pointer = format!("/services/{}", escape(name));
+
+        let mut check = |key: &str, section: &str, known: &[String], found: Vec<(String, String)>| {
+            for (segment, reference) in found {
+                if !known.contains(&reference) {
+                    violations.push(Violation {
+                        pointer: format!("{pointer}/{key}/{}", escape(&segment)),
+                        message: format!("unknown {section} `{reference}`"),
+                    });
+                }
+            }
+        };
+
+        if let Some(references) = service.get("depends_on") {
+            check("depends_on", "service", &services_declared, references_of(references, None));
+        }
+
+        if let Some(references) = service.get("networks") {
+            check("networks", "network", &networks, references_of(references, None));
+        }
+
+        if let Some(mounts) = service.get("volumes").and_then(hcl::Value::as_array) {
+            check(
+                "volumes",
+                "volume",
+                &volumes,
+                mounts.iter().enumerate().filter_map(|(index, mount)| Some((index.to_string(), volume(mount)?))).collect(),
+            );
+        }
+
+        if let Some(references) = service.get("configs") {
+            check("configs", "config", &configs, references_of(references, Some("source")));
+        }
+
+        if let Some(references) = service.get("secrets") {
+            check("secrets", "secret", &secrets, references_of(references, Some("source")));
+        }
+    }
+
+    match violations.is_empty() {
+        true => Ok(()),
+        false => Err(Error::Compose(violations)),
+    }
+}
+
+/// Orders the top-level sections as the compose specification lists them, followed by the others such as `x-` extensions.
+fn order(value: hcl::Value) -> hcl::Value {
+    let hcl::Value::Object(mut obj) = value else { return value };
+    let mut ordered = hcl::Map::new();
+
+    for key in ORDER {
+        if let Some(section) = obj.shift_remove(*key) {
+            ordered.insert((*key).to_owned(), section);
+        }
+    }
+
+    ordered.extend(obj);
+    hcl::Value::Object(ordered)
+}
+
+/// The names referenced by a list of names, a list of objects with a `key` naming the entry, or a map keyed by name,
+/// each with the JSON pointer segment it is found at.
+fn references_of(value: &hcl::Value, key: Option<&str>) -> Vec<(String, String)> {
+    match value {
+        hcl::Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| match (item, key) {
+                (hcl::Value::String(name), _) => Some((index.to_string(), name.to_owned())),
+                (hcl::Value::Object(obj), Some(key)) => obj.get(key).and_then(hcl::Value::as_str).map(|name| (index.to_string(), name.to_owned())),
+                _ => None,
+            })
+            .collect(),
+        hcl::Value::Object(obj) => obj.keys().map(|name| (name.to_owned(), name.to_owned())).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The named volume a service mount uses, if it is not a bind mount, tmpfs or anonymous volume.
+fn volume(mount: &hcl::Value) -> Option<String> {
+    match mount {
+        hcl::Value::String(mount) => {
+            let (source, _) = mount.split_once(':')?;
+            let path = source.is_empty() || source.contains('/') || source.starts_with('.') || source.starts_with('~');
+            (!path).then(|| source.to_owned())
+        }
+        hcl::Value::Object(obj) => match (obj.get("type").and_then(hcl::Value::as_str), obj.get("source").and_then(hcl::Value::as_str)) {
+            (Some("volume"), Some(source)) => Some(source.to_owned()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn escape(segment: &str) -> String { segment.replace('~', "~0").replace('/', "~1") }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, HclConverter};
+    use hcl::eval::Evaluate;
+
+    fn value(source: &str) -> Result<hcl::Value, Error> { HclConverter::builder().source(source).build()?.value() }
+
+    #[test]
+    fn declares_generated_services() {
+        let source = r#"
+meta {
+  kind = "docker"
+}
+
+locals {
+  workers = ["a", "b"]
+}
+
+services {
+  web {
+    image = "nginx"
+  }
+
+  dynamic "worker" {
+    for_each = local.workers
+    labels   = [worker.value]
+
+    content {
+      image = "worker"
+    }
+  }
+}
+
+names = compose.services
+"#;
+
+        let value = value(source).unwrap();
+        assert_eq!(value.as_object().unwrap()["names"], hcl::Value::from_iter(["web", "worker"]));
+    }
+
+    #[test]
+    fn leaves_out_dropped_services() {
+        let source = r#"
+meta {
+  kind = "docker"
+}
+
+services {
+  web {
+    image = "nginx"
+  }
+
+  debug {
+    when  = false
+    image = "busybox"
+  }
+}
+
+names = compose.services
+"#;
+
+        let value = value(source).unwrap();
+        assert_eq!(value.as_object().unwrap()["names"], hcl::Value::from_iter(["web"]));
+    }
+
+    #[test]
+    fn declares_names_under_compose() {
+        let source = "meta {\n  kind = \"docker\"\n}\n\nservices {\n  web {\n    image = \"nginx\"\n  }\n}\n";
+        let hcl = HclConverter::builder().source(source).build().unwrap();
+
+        let lookup = |name: &str| hcl.scoped(|ctx| hcl::Expression::from(hcl::expr::Variable::unchecked(name)).evaluate(ctx));
+        assert!(lookup("services").is_err());
+        assert_eq!(lookup("compose").unwrap().as_object().unwrap()["services"], hcl::Value::from_iter(["web"]));
+    }
+}
@@ -0,0 +1,142 @@
+use super::Kind;
+use crate::{Error, Language};
+
+/// Resources that can be declared by their block type, with their API version and whether they live in a namespace.
+const RESOURCES: &[(&str, &str, bool)] = &[
+    ("Namespace", "v1", false),
+    ("ConfigMap", "v1", true),
+    ("Secret", "v1", true),
+    ("Service", "v1", true),
+    ("ServiceAccount", "v1", true),
+    ("Pod", "v1", true),
+    ("PersistentVolume", "v1", false),
+    ("PersistentVolumeClaim", "v1", true),
+    ("Deployment", "apps/v1", true),
+    ("StatefulSet", "apps/v1", true),
+    ("DaemonSet", "apps/v1", true),
+    ("ReplicaSet", "apps/v1", true),
+    ("Job", "batch/v1", true),
+    ("CronJob", "batch/v1", true),
+    ("Ingress", "networking.k8s.io/v1", true),
+    ("IngressClass", "networking.k8s.io/v1", false),
+    ("NetworkPolicy", "networking.k8s.io/v1", true),
+    ("Role", "rbac.authorization.k8s.io/v1", true),
+    ("RoleBinding", "rbac.authorization.k8s.io/v1", true),
+    ("ClusterRole", "rbac.authorization.k8s.io/v1", false),
+    ("ClusterRoleBinding", "rbac.authorization.k8s.io/v1", false),
+    ("HorizontalPodAutoscaler", "autoscaling/v2", true),
+    ("PodDisruptionBudget", "policy/v1", true),
+    ("StorageClass", "storage.k8s.io/v1", false),
+    ("CustomResourceDefinition", "apiextensions.k8s.io/v1", false),
+];
+
+/// Manifests, `meta.kind = "kubernetes"`.
+pub(super) struct Kubernetes;
+
+impl Kind for Kubernetes {
+    fn name(&self) -> &str { "kubernetes" }
+
+    fn transform(&self, value: hcl::Value, meta: &hcl::Map<String, hcl::Value>) -> Result<hcl::Value, Error> { manifests(value, meta) }
+
+    fn format(&self) -> Option<Language> { Some(Language::YAML) }
+
+    /// Every manifest is a document of its own in the stream.
+    fn stream(&self, value: &hcl::Value) -> Option<Vec<hcl::Value>> { Some(items(value).to_vec()) }
+}
+
+/// Turns the top-level `<type> "<name>" {}` blocks of a document into a `v1/List` of manifests.
+///
+/// Block types name the resource kind in any case, with or without underscores, so `config_map` and
+/// `ConfigMap` both declare a `ConfigMap`. Other kinds, such as custom resources, set `apiVersion` and
+/// `kind` themselves. `meta.labels` are added to every manifest and `meta.namespace` to namespaced ones,
+/// both giving way to what the manifest sets.
+fn manifests(value: hcl::Value, meta: &hcl::Map<String, hcl::Value>) -> Result<hcl::Value, Error> {
+    let labels = match meta.get("labels") {
+        None => hcl::Map::new(),
+        Some(hcl::Value::Object(labels)) => labels.to_owned(),
+        Some(_) => return Err(Error::InvalidManifest("`meta.labels` needs to be an object".into())),
+    };
+
+    let namespace = match meta.get("namespace") {
+        None => None,
+        Some(hcl::Value::String(namespace)) => Some(namespace.as_str()),
+        Some(_) => return Err(Error::InvalidManifest("`meta.namespace` needs to be a string".into())),
+    };
+
+    let hcl::Value::Object(obj) = value else { return Err(Error::InvalidRoot) };
+    let mut items = Vec::new();
+
+    for (ident, resources) in obj {
+        let hcl::Value::Object(resources) = resources else {
+            return Err(Error::InvalidManifest(format!("`{ident}` is not a resource, declare it as `{ident} \"<name>\" {{}}`")));
+        };
+
+        let known = RESOURCES.iter().find(|(kind, ..)| kind.eq_ignore_ascii_case(&ident.replace('_', "")));
+
+        for (name, resource) in resources {
+            let hcl::Value::Object(mut resource) = resource else {
+                return Err(Error::InvalidManifest(format!("`{ident}.{name}` is not a resource, declare it as `{ident} \"{name}\" {{}}`")));
+            };
+
+            let api_version = resource.shift_remove("apiVersion").or_else(|| known.map(|(_, version, _)| hcl::Value::from(*version)));
+            let kind = resource.shift_remove("kind").or_else(|| known.map(|(kind, ..)| hcl::Value::from(*kind)));
+
+            let (Some(api_version), Some(kind)) = (api_version, kind) else {
+                return Err(Error::InvalidManifest(format!("Unknown resource type `{ident}` of `{ident}.{name}`, set its `apiVersion` and `kind`")));
+            };
+
+            let mut metadata = match resource.shift_remove("metadata") {
+                None => hcl::Map::new(),
+                Some(hcl::Value::Object(metadata)) => metadata,
+                Some(_) => return Err(Error::InvalidManifest(format!("`metadata` of `{ident}.{name}` needs to be an object"))),
+            };
+
+            metadata.entry("name".into()).or_insert_with(|| hcl::Value::from(name.as_str()));
+
+            // custom resources are assumed to be namespaced, cluster-scoped ones can set `namespace = null`
+            if let Some(namespace) = namespace.filter(|_| known.is_none_or(|(.., namespaced)| *namespaced)) {
+                metadata.entry("namespace".into()).or_insert_with(|| hcl::Value::from(namespace));
+            }
+
+            if metadata.get("namespace").is_some_and(hcl::Value::is_null) {
+                metadata.shift_remove("namespace");
+            }
+
+            if !labels.is_empty() {
+                let mut merged = labels.to_owned();
+
+                match metadata.shift_remove("labels") {
+                    None => {}
+                    Some(hcl::Value::Object(own)) => merged.extend(own),
+                    Some(_) => return Err(Error::InvalidManifest(format!("`metadata.labels` of `{ident}.{name}` needs to be an object"))),
+                }
+
+                metadata.insert("labels".into(), hcl::Value::Object(merged));
+            }
+
+            let mut manifest = hcl::Map::new();
+            manifest.insert("apiVersion".into(), api_version);
+            manifest.insert("kind".into(), kind);
+            manifest.insert("metadata".into(), hcl::Value::Object(metadata));
+            manifest.extend(resource);
+
+            items.push(hcl::Value::Object(manifest));
+        }
+    }
+
+    let mut list = hcl::Map::new();
+    list.insert("apiVersion".into(), hcl::Value::from("v1"));
+    list.insert("kind".into(), hcl::Value::from("List"));
+    list.insert("items".into(), hcl::Value::Array(items));
+
+    Ok(hcl::Value::Object(list))
+}
+
+/// The manifests of a list made by [`manifests`].
+fn items(list: &hcl::Value) -> &[hcl::Value] {
+    list.as_object()
+        .and_then(|list| list.get("items"))
+        .and_then(hcl::Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
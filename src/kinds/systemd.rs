@@ -0,0 +1,98 @@
+use super::Kind;
+use crate::{numbers, Error, Language};
+
+/// Unit files, `meta.kind = "systemd"`.
+///
+/// Top-level blocks are the sections of the unit, `service {}` renders as `[Service]`. Lists repeat their key
+/// for every item, objects such as `Environment = { PORT = 8080 }` repeat it as `Environment=PORT=8080`, and
+/// booleans render as `yes` and `no`.
+pub(super) struct Systemd;
+
+impl Kind for Systemd {
+    fn name(&self) -> &str { "systemd" }
+
+    fn validate(&self, value: &hcl::Value) -> Result<(), Error> {
+        let Some(obj) = value.as_object() else { return Err(Error::InvalidRoot) };
+
+        for (name, section) in obj {
+            let Some(section) = section.as_object() else {
+                return Err(Error::InvalidKind(format!("`{name}` is not a section of the unit, declare it as `{name} {{}}`")));
+            };
+
+            for (key, value) in section {
+                let values: Vec<&hcl::Value> = match value {
+                    hcl::Value::Array(items) => items.iter().collect(),
+                    hcl::Value::Object(entries) => entries.values().collect(),
+                    value => vec![value],
+                };
+
+                for value in values {
+                    match value {
+                        hcl::Value::Array(_) | hcl::Value::Object(_) => return Err(Error::InvalidKind(format!("`{name}.{key}` cannot nest lists or objects in a unit file"))),
+                        hcl::Value::String(text) if text.contains('\n') => return Err(Error::InvalidKind(format!("`{name}.{key}` cannot span lines in a unit file"))),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn format(&self) -> Option<Language> { Some(Language::INI) }
+
+    fn render(&self, value: &hcl::Value, lang: &Language) -> Option<Result<String, Error>> {
+        match lang {
+            Language::INI => Some(Ok(unit(value))),
+            _ => None,
+        }
+    }
+}
+
+fn unit(value: &hcl::Value) -> String {
+    let mut sections = Vec::new();
+
+    for (name, section) in value.as_object().into_iter().flatten() {
+        let mut lines = vec![format!("[{}]", title(name))];
+
+        for (key, value) in section.as_object().into_iter().flatten() {
+            match value {
+                hcl::Value::Array(items) => lines.extend(items.iter().filter_map(scalar).map(|item| format!("{key}={item}"))),
+                hcl::Value::Object(entries) => lines.extend(entries.iter().filter_map(|(name, item)| Some(format!("{key}={}", assignment(name, &scalar(item)?))))),
+                value => lines.extend(scalar(value).map(|value| format!("{key}={value}"))),
+            }
+        }
+
+        sections.push(lines.join("\n"));
+    }
+
+    sections.join("\n\n") + "\n"
+}
+
+/// The section name for a block, `service` becomes `Service`.
+fn title(name: &str) -> String {
+    let mut chars = name.chars();
+    chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+/// An `Environment=` style assignment, quoted when the value has spaces.
+fn assignment(name: &str, value: &str) -> String {
+    match value.contains(char::is_whitespace) {
+        true => format!("\"{name}={}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+        false => format!("{name}={value}"),
+    }
+}
+
+fn scalar(value: &hcl::Value) -> Option<String> {
+    match value {
+        hcl::Value::Null => None,
+        hcl::Value::Bool(true) => Some("yes".into()),
+        hcl::Value::Bool(false) => Some("no".into()),
+        hcl::Value::Number(number) => Some(match numbers::as_integer(number) {
+            Some(int) => int.to_string(),
+            None => number.to_string(),
+        }),
+        hcl::Value::String(text) => Some(text.to_owned()),
+        value => Some(value.to_string()),
+    }
+}
@@ -0,0 +1,25 @@
+//! Ship renders HCL configuration files to JSON, YAML and TOML, with a library of
+//! functions available to expressions.
+//!
+//! Documents are loaded with [`HclConverter::builder`], which reads the source, declares its
+//! `locals`, variable blocks and `meta`, and returns a converter that can be evaluated to a
+//! [`hcl::Value`] or rendered to a string in any [`Language`].
+
+mod builder;
+mod converter;
+mod error;
+mod macros;
+mod numbers;
+
+pub mod functions;
+pub mod kinds;
+pub mod nulls;
+pub mod plugins;
+pub mod schema;
+pub mod secrets;
+pub mod sensitive;
+
+pub use builder::Builder;
+pub use converter::{Artifact, Evaluated, HclConverter, Language};
+pub use error::Error;
+pub use hcl;
@@ -1,249 +1,115 @@
+/// Declares functions into a context and returns their [`Signature`](crate::functions::Signature)s.
+///
+/// Each entry is `rust_fn => ns::name(param: Type, ..variadic: Type)`, with an optional doc
+/// comment used as the description and an `#[example = "..."]` attribute.
 #[macro_export]
 macro_rules! declare_fns {
     (@param_type Array) => {
-        hcl::eval::ParamType::Array(Box::new(hcl::eval::ParamType::Any))
+        $crate::hcl::eval::ParamType::Array(Box::new($crate::hcl::eval::ParamType::Any))
     };
     (@param_type Object) => {
-        hcl::eval::ParamType::Object(Box::new(hcl::eval::ParamType::Any))
+        $crate::hcl::eval::ParamType::Object(Box::new($crate::hcl::eval::ParamType::Any))
     };
     (@param_type Nullable) => {
-        hcl::eval::ParamType::Nullable(Box::new(hcl::eval::ParamType::String))
+        $crate::hcl::eval::ParamType::Nullable(Box::new($crate::hcl::eval::ParamType::String))
     };
     (@param_type $param:ident) => {
-        hcl::eval::ParamType::$param
+        $crate::hcl::eval::ParamType::$param
     };
 
-    ($ctx:expr, { $($rest:tt)* }) => {
-        declare_fns!(@process_fns $ctx, $($rest)*)
-    };
-
-    (@process_fns $ctx:expr) => {};
-
-    (@process_fns $ctx:expr, $fn_name:ident => $ns:ident::$func:ident($($param:ident),+ , ..$var_param:ident), $($rest:tt)*) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func))
-                .with_namespace(vec![stringify!($ns)]);
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            $(
-                builder = builder.param(declare_fns!(@param_type $param));
-            )*
-            builder = builder.variadic_param(declare_fns!(@param_type $var_param));
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
+    (@params $builder:ident, $params:ident;) => {};
 
-            declare_fns!(@process_fns $ctx, $($rest)*)
-        }
+    (@params $builder:ident, $params:ident; ..$name:ident: $kind:ident $(,)?) => {
+        $builder = $builder.variadic_param($crate::declare_fns!(@param_type $kind));
+        $params.push($crate::functions::Param::new(stringify!($name), stringify!($kind), true));
     };
 
-    (@process_fns $ctx:expr, $fn_name:ident => $func:ident($($param:ident),+ , ..$var_param:ident), $($rest:tt)*) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func));
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            $(
-                builder = builder.param(declare_fns!(@param_type $param));
-            )*
-            builder = builder.variadic_param(declare_fns!(@param_type $var_param));
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-
-            declare_fns!(@process_fns $ctx, $($rest)*)
-        }
+    (@params $builder:ident, $params:ident; $name:ident: $kind:ident $(, $($rest:tt)*)?) => {
+        $builder = $builder.param($crate::declare_fns!(@param_type $kind));
+        $params.push($crate::functions::Param::new(stringify!($name), stringify!($kind), false));
+        $crate::declare_fns!(@params $builder, $params; $($($rest)*)?);
     };
 
-    (@process_fns $ctx:expr, $fn_name:ident => $ns:ident::$func:ident($($param:ident),+ , ..$var_param:ident)) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func))
-                .with_namespace(vec![stringify!($ns)]);
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            $(
-                builder = builder.param(declare_fns!(@param_type $param));
-            )*
-            builder = builder.variadic_param(declare_fns!(@param_type $var_param));
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-        }
+    (@name $func:ident) => {
+        $crate::hcl::expr::FuncName::new(stringify!($func))
     };
-
-    (@process_fns $ctx:expr, $fn_name:ident => $func:ident($($param:ident),+ , ..$var_param:ident)) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func));
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            $(
-                builder = builder.param(declare_fns!(@param_type $param));
-            )*
-            builder = builder.variadic_param(declare_fns!(@param_type $var_param));
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-        }
-    };
-
-    (@process_fns $ctx:expr, $fn_name:ident => $ns:ident::$func:ident(..$param:ident), $($rest:tt)*) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func))
-                .with_namespace(vec![stringify!($ns)]);
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            builder = builder.variadic_param(declare_fns!(@param_type $param));
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-
-            declare_fns!(@process_fns $ctx, $($rest)*)
-        }
-    };
-
-    (@process_fns $ctx:expr, $fn_name:ident => $func:ident(..$param:ident), $($rest:tt)*) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func));
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            builder = builder.variadic_param(declare_fns!(@param_type $param));
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-
-            declare_fns!(@process_fns $ctx, $($rest)*)
-        }
-    };
-
-    (@process_fns $ctx:expr, $fn_name:ident => $ns:ident::$func:ident($($param:ident),* $(,)?), $($rest:tt)*) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func))
-                .with_namespace(vec![stringify!($ns)]);
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            $(
-                builder = builder.param(declare_fns!(@param_type $param));
-            )*
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-
-            declare_fns!(@process_fns $ctx, $($rest)*)
-        }
+    (@name $ns:ident::$func:ident) => {
+        $crate::hcl::expr::FuncName::new(stringify!($func)).with_namespace(vec![stringify!($ns)])
     };
 
-    (@process_fns $ctx:expr, $fn_name:ident => $func:ident($($param:ident),* $(,)?), $($rest:tt)*) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func));
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            $(
-                builder = builder.param(declare_fns!(@param_type $param));
-            )*
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-
-            declare_fns!(@process_fns $ctx, $($rest)*)
-        }
+    (@example) => {
+        None
     };
-
-    (@process_fns $ctx:expr, $fn_name:ident => $ns:ident::$func:ident(..$param:ident), $($rest:tt)*) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func))
-                .with_namespace(vec![stringify!($ns)]);
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            builder = builder.variadic_param(declare_fns!(@param_type $param));
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-
-            declare_fns!(@process_fns $ctx, $($rest)*)
-        }
+    (@example $example:literal) => {
+        Some($example)
     };
 
-    (@process_fns $ctx:expr, $fn_name:ident => $func:ident(..$param:ident), $($rest:tt)*) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func));
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            builder = builder.variadic_param(declare_fns!(@param_type $param));
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
+    ($ctx:expr, { $($(#[doc = $doc:literal])* $(#[example = $example:literal])? $fn_name:ident => $($path:ident)::+ ($($params:tt)*)),* $(,)? }) => {{
+        #[allow(unused_mut)]
+        let mut catalog: Vec<$crate::functions::Signature> = Vec::new();
 
-            declare_fns!(@process_fns $ctx, $($rest)*)
-        }
-    };
-
-    (@process_fns $ctx:expr, $fn_name:ident => $ns:ident::$func:ident($($param:ident),* $(,)?), $($rest:tt)*) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func))
-                .with_namespace(vec![stringify!($ns)]);
+        $(
             #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            $(
-                builder = builder.param(declare_fns!(@param_type $param));
-            )*
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-
-            declare_fns!(@process_fns $ctx, $($rest)*)
-        }
-    };
-
-    (@process_fns $ctx:expr, $fn_name:ident => $func:ident($($param:ident),* $(,)?), $($rest:tt)*) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func));
+            let mut builder = $crate::hcl::eval::FuncDef::builder();
             #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            $(
-                builder = builder.param(declare_fns!(@param_type $param));
-            )*
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
+            let mut params = Vec::new();
 
-            declare_fns!(@process_fns $ctx, $($rest)*)
-        }
-    };
+            $crate::declare_fns!(@params builder, params; $($params)*);
 
-    (@process_fns $ctx:expr, $fn_name:ident => $ns:ident::$func:ident(..$param:ident)) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func))
-                .with_namespace(vec![stringify!($ns)]);
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            builder = builder.variadic_param(declare_fns!(@param_type $param));
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-        }
-    };
+            let func_name = $crate::declare_fns!(@name $($path)::+);
+            let description = concat!($($doc, "\n"),*);
 
-    (@process_fns $ctx:expr, $fn_name:ident => $func:ident(..$param:ident)) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func));
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            builder = builder.variadic_param(declare_fns!(@param_type $param));
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-        }
-    };
+            $ctx.declare_func(func_name.clone(), builder.build(|args: $crate::hcl::eval::FuncArgs| $crate::sensitive::call($fn_name, args)));
+            catalog.push($crate::functions::Signature::new(func_name, params, description, $crate::declare_fns!(@example $($example)?)));
+        )*
 
-    (@process_fns $ctx:expr, $fn_name:ident => $ns:ident::$func:ident($($param:ident),* $(,)?)) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func))
-                .with_namespace(vec![stringify!($ns)]);
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            $(
-                builder = builder.param(declare_fns!(@param_type $param));
-            )*
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-        }
-    };
+        catalog
+    }};
+}
 
-    (@process_fns $ctx:expr, $fn_name:ident => $func:ident($($param:ident),* $(,)?)) => {
-        {
-            let func_name = hcl::expr::FuncName::new(stringify!($func));
-            #[allow(unused_mut)]
-            let mut builder = hcl::eval::FuncDef::builder();
-            $(
-                builder = builder.param(declare_fns!(@param_type $param));
-            )*
-            let func_def = builder.build($fn_name);
-            $ctx.declare_func(func_name, func_def);
-        }
+/// Builds a 16x16 table of `fn` pointers to `$slot::<N>`. Evaluation functions are plain
+/// `fn` pointers and cannot capture state, so dynamic functions are bound to a slot instead.
+macro_rules! slots {
+    ($slot:ident) => {
+        [
+            $crate::macros::slots!(@row $slot, 0),
+            $crate::macros::slots!(@row $slot, 1),
+            $crate::macros::slots!(@row $slot, 2),
+            $crate::macros::slots!(@row $slot, 3),
+            $crate::macros::slots!(@row $slot, 4),
+            $crate::macros::slots!(@row $slot, 5),
+            $crate::macros::slots!(@row $slot, 6),
+            $crate::macros::slots!(@row $slot, 7),
+            $crate::macros::slots!(@row $slot, 8),
+            $crate::macros::slots!(@row $slot, 9),
+            $crate::macros::slots!(@row $slot, 10),
+            $crate::macros::slots!(@row $slot, 11),
+            $crate::macros::slots!(@row $slot, 12),
+            $crate::macros::slots!(@row $slot, 13),
+            $crate::macros::slots!(@row $slot, 14),
+            $crate::macros::slots!(@row $slot, 15)
+        ]
+    };
+    (@row $slot:ident, $row:literal) => {
+        [
+            $slot::<{ $row * 16 + 0 }>,
+            $slot::<{ $row * 16 + 1 }>,
+            $slot::<{ $row * 16 + 2 }>,
+            $slot::<{ $row * 16 + 3 }>,
+            $slot::<{ $row * 16 + 4 }>,
+            $slot::<{ $row * 16 + 5 }>,
+            $slot::<{ $row * 16 + 6 }>,
+            $slot::<{ $row * 16 + 7 }>,
+            $slot::<{ $row * 16 + 8 }>,
+            $slot::<{ $row * 16 + 9 }>,
+            $slot::<{ $row * 16 + 10 }>,
+            $slot::<{ $row * 16 + 11 }>,
+            $slot::<{ $row * 16 + 12 }>,
+            $slot::<{ $row * 16 + 13 }>,
+            $slot::<{ $row * 16 + 14 }>,
+            $slot::<{ $row * 16 + 15 }>
+        ]
     };
 }
+
+pub(crate) use slots;
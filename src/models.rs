@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use ship::secrets::Vault;
 use std::path::PathBuf;
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -8,13 +9,34 @@ pub(crate) struct Config {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub(crate) struct Settings {
+    #[serde(default = "default_listen")]
     pub(crate) listen: String,
     pub(crate) storage: PathBuf,
+    /// Server log level, `debug` also logs the files each document extends.
+    #[serde(default = "default_log")]
+    pub(crate) log: String,
     pub(crate) vault: Option<Vault>,
+    #[serde(default)]
+    pub(crate) functions: Functions,
+    #[serde(default)]
+    pub(crate) plugin: hcl::Map<String, Plugin>,
 }
 
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Functions {
+    #[serde(default)]
+    pub(crate) enable: Vec<String>,
+    #[serde(default)]
+    pub(crate) disable: Vec<String>,
+}
+
+fn default_listen() -> String { "127.0.0.1:8080".into() }
+
+fn default_log() -> String { "info".into() }
+
 #[derive(Clone, Serialize, Deserialize)]
-pub(crate) struct Vault {
-    pub(crate) url: String,
-    pub(crate) token: String,
+pub(crate) struct Plugin {
+    pub(crate) source: PathBuf,
+    pub(crate) fuel: Option<u64>,
+    pub(crate) memory: Option<usize>,
 }
@@ -0,0 +1,96 @@
+//! What happens to `null` values when a document is rendered.
+//!
+//! JSON and YAML can represent `null`, TOML cannot. The [`Policy`] of a document, from the request or
+//! `meta.nulls`, decides whether nulls are kept, left out, rejected or rendered as the string `"null"`
+//! in every format.
+
+use crate::{Error, Language};
+
+use std::str::FromStr;
+
+/// How `null` values are rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Policy {
+    /// Nulls are rendered as nulls, and as the string `"null"` in TOML.
+    #[default]
+    Keep,
+    /// Object keys and list items that are null are left out.
+    Omit,
+    /// Rendering fails if the output contains a null.
+    Error,
+    /// Nulls are rendered as the string `"null"`.
+    String,
+}
+
+impl FromStr for Policy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(Policy::Keep),
+            "omit" => Ok(Policy::Omit),
+            "error" => Ok(Policy::Error),
+            "string" => Ok(Policy::String),
+            _ => Err(Error::UnknownNullPolicy(s.to_owned())),
+        }
+    }
+}
+
+impl Policy {
+    /// Whether `lang` renders the nulls left in the output as strings.
+    pub fn is_lossy(&self, lang: &Language) -> bool {
+        match self {
+            Policy::Keep => *lang == Language::TOML,
+            Policy::String => true,
+            Policy::Omit | Policy::Error => false,
+        }
+    }
+
+    /// Applies the policy to a value about to be rendered in `lang`, returning it with the JSON pointers of the
+    /// nulls that are rendered as strings.
+    pub(crate) fn apply(&self, value: hcl::Value, lang: &Language) -> Result<(hcl::Value, Vec<String>), Error> {
+        let mut nulls = Vec::new();
+        let value = self.walk(value, &mut String::new(), &mut nulls).unwrap_or(hcl::Value::Null);
+
+        match self {
+            Policy::Error if !nulls.is_empty() => Err(Error::NullOutput(nulls)),
+            _ if self.is_lossy(lang) => Ok((value, nulls)),
+            _ => Ok((value, Vec::new())),
+        }
+    }
+
+    /// The value with the nulls replaced or removed, collecting their pointers into `nulls`.
+    fn walk(&self, value: hcl::Value, pointer: &mut String, nulls: &mut Vec<String>) -> Option<hcl::Value> {
+        match value {
+            hcl::Value::Null => {
+                nulls.push(pointer.to_owned());
+
+                match self {
+                    Policy::Omit => None,
+                    Policy::String => Some(hcl::Value::from("null")),
+                    Policy::Keep | Policy::Error => Some(hcl::Value::Null),
+                }
+            }
+            hcl::Value::Array(items) => {
+                let items = items.into_iter().enumerate().filter_map(|(index, item)| self.walk_at(&index.to_string(), item, pointer, nulls));
+                Some(hcl::Value::Array(items.collect()))
+            }
+            hcl::Value::Object(obj) => {
+                let entries = obj.into_iter().filter_map(|(key, item)| Some((key.to_owned(), self.walk_at(&key, item, pointer, nulls)?)));
+                Some(hcl::Value::Object(entries.collect()))
+            }
+            value => Some(value),
+        }
+    }
+
+    fn walk_at(&self, segment: &str, value: hcl::Value, pointer: &mut String, nulls: &mut Vec<String>) -> Option<hcl::Value> {
+        let len = pointer.len();
+
+        pointer.push('/');
+        pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+        let value = self.walk(value, pointer, nulls);
+        pointer.truncate(len);
+
+        value
+    }
+}
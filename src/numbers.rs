@@ -0,0 +1,160 @@
+//! Number conversions that keep integers integers.
+//!
+//! HCL numbers are 64-bit integers or floats. Functions and output formats convert through these
+//! helpers rather than `f64`, so `8080` stays `8080` and large IDs keep their digits. Integers that
+//! do not fit into 64 bits are an error rather than a rounded float.
+
+use serde_json::Number as JsonNumber;
+use toml::Value as TomlValue;
+
+/// Parses a number, as an integer when it is written as one.
+pub(crate) fn parse(text: &str) -> Option<hcl::Value> { parse_integer(text).or_else(|| text.parse::<f64>().ok().and_then(from_f64).map(hcl::Value::Number)) }
+
+/// Parses a base 10 integer, `None` if it does not fit into 64 bits.
+pub(crate) fn parse_integer(text: &str) -> Option<hcl::Value> {
+    match (text.parse::<i64>(), text.parse::<u64>()) {
+        (Ok(int), _) => Some(int.into()),
+        (_, Ok(int)) => Some(int.into()),
+        _ => None,
+    }
+}
+
+/// A float result, as an integer when it has no fraction.
+///
+/// `None` for floats that are not finite, and for integral floats beyond 64 bits which `hcl` would
+/// otherwise clamp to `i64::MAX`.
+pub(crate) fn from_f64(float: f64) -> Option<hcl::Number> {
+    match float.is_finite() && float.fract() == 0.0 {
+        true => from_integer(float as i128),
+        false => hcl::Number::from_f64(float),
+    }
+}
+
+/// The number as an integer, if it is one that fits into 64 bits.
+pub(crate) fn as_integer(number: &hcl::Number) -> Option<i128> { number.as_i64().map(i128::from).or_else(|| number.as_u64().map(i128::from)) }
+
+/// An integer result, `None` if it does not fit into 64 bits.
+pub(crate) fn from_integer(int: i128) -> Option<hcl::Number> {
+    match (i64::try_from(int), u64::try_from(int)) {
+        (Ok(int), _) => Some(hcl::Number::from(int)),
+        (_, Ok(int)) => Some(hcl::Number::from(int)),
+        _ => None,
+    }
+}
+
+pub(crate) fn to_json(number: &hcl::Number) -> JsonNumber {
+    match (number.as_i64(), number.as_u64()) {
+        (Some(int), _) => JsonNumber::from(int),
+        (_, Some(int)) => JsonNumber::from(int),
+        _ => number.as_f64().and_then(JsonNumber::from_f64).unwrap_or_else(|| JsonNumber::from(0)),
+    }
+}
+
+pub(crate) fn from_json(number: &JsonNumber) -> Result<hcl::Value, String> {
+    match (number.as_i64(), number.as_u64()) {
+        (Some(int), _) => Ok(int.into()),
+        (_, Some(int)) => Ok(int.into()),
+        _ => number
+            .as_f64()
+            .and_then(from_f64)
+            .map(hcl::Value::Number)
+            .ok_or_else(|| format!("{number} does not fit into a 64-bit number")),
+    }
+}
+
+pub(crate) fn to_yaml(number: &hcl::Number) -> serde_yaml_ng::Number {
+    match (number.as_i64(), number.as_u64()) {
+        (Some(int), _) => int.into(),
+        (_, Some(int)) => int.into(),
+        _ => number.as_f64().unwrap_or_default().into(),
+    }
+}
+
+/// TOML integers are signed 64-bit, `None` for larger ones.
+pub(crate) fn to_toml(number: &hcl::Number) -> Option<TomlValue> {
+    match (number.as_i64(), number.as_u64()) {
+        (Some(int), _) => Some(TomlValue::Integer(int)),
+        (_, Some(_)) => None,
+        _ => number.as_f64().map(TomlValue::Float),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, HclConverter, Language};
+
+    const SOURCE: &str = "meta {\n  file = \"ids\"\n}\n\nsigned   = 9223372036854775807\nunsigned = 9223372036854775808\nlargest  = 18446744073709551615\n";
+
+    fn render(source: &str, lang: &Language) -> Result<String, Error> { HclConverter::builder().source(source).build()?.render(lang) }
+
+    #[test]
+    fn parses_integers_at_the_boundaries() {
+        assert_eq!(parse_integer("9223372036854775807"), Some(hcl::Value::from(i64::MAX)));
+        assert_eq!(parse_integer("9223372036854775808"), Some(hcl::Value::from(i64::MAX as u64 + 1)));
+        assert_eq!(parse_integer("18446744073709551615"), Some(hcl::Value::from(u64::MAX)));
+        assert_eq!(parse_integer("18446744073709551616"), None);
+        assert_eq!(parse_integer("-9223372036854775809"), None);
+    }
+
+    #[test]
+    fn decodes_json_integers_at_the_boundaries() {
+        for int in ["9223372036854775807", "9223372036854775808", "18446744073709551615"] {
+            let number: JsonNumber = int.parse().unwrap();
+            assert_eq!(from_json(&number).unwrap().to_string(), int);
+        }
+
+        assert!(from_json(&"1e30".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn renders_json_exactly() {
+        let json = render(SOURCE, &Language::JSON).unwrap();
+
+        assert!(json.contains("\"signed\": 9223372036854775807"));
+        assert!(json.contains("\"unsigned\": 9223372036854775808"));
+        assert!(json.contains("\"largest\": 18446744073709551615"));
+    }
+
+    #[test]
+    fn renders_yaml_exactly() {
+        let yaml = render(SOURCE, &Language::YAML).unwrap();
+
+        assert!(yaml.contains("signed: 9223372036854775807"));
+        assert!(yaml.contains("unsigned: 9223372036854775808"));
+        assert!(yaml.contains("largest: 18446744073709551615"));
+    }
+
+    #[test]
+    fn renders_toml_up_to_i64_max() {
+        let toml = render("meta {\n  file = \"ids\"\n}\n\nsigned = 9223372036854775807\n", &Language::TOML).unwrap();
+        assert_eq!(toml.trim(), "signed = 9223372036854775807");
+    }
+
+    #[test]
+    fn rejects_toml_beyond_i64_max() {
+        match render(SOURCE, &Language::TOML) {
+            Err(Error::NumberOutput(pointers)) => assert_eq!(pointers, ["/unsigned", "/largest"]),
+            other => panic!("expected the unsigned integers to be rejected, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn renders_ini_exactly() {
+        let source =
+            "meta {\n  file = \"ids.service\"\n  kind = \"systemd\"\n}\n\nservice {\n  Signed   = 9223372036854775807\n  Unsigned = 9223372036854775808\n  Largest  = 18446744073709551615\n}\n";
+        let unit = render(source, &Language::INI).unwrap();
+
+        assert!(unit.contains("Signed=9223372036854775807"));
+        assert!(unit.contains("Unsigned=9223372036854775808"));
+        assert!(unit.contains("Largest=18446744073709551615"));
+    }
+
+    #[test]
+    fn rejects_decoded_toml_beyond_i64_max() {
+        let source = "meta {\n  file = \"ids\"\n}\n\nid = decode::json(\"12345678901234567890\")\n";
+
+        assert!(render(source, &Language::JSON).unwrap().contains("\"id\": 12345678901234567890"));
+        assert!(matches!(render(source, &Language::TOML), Err(Error::NumberOutput(_))));
+    }
+}
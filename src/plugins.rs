@@ -0,0 +1,316 @@
+//! WebAssembly plugins contributing functions under their own namespace.
+//!
+//! A plugin is a core WebAssembly module without imports that exports its `memory` and an
+//! `alloc(len: i32) -> i32` function. Every other export with the signature
+//! `(ptr: i32, len: i32) -> i64` becomes the function `<plugin>::<export>`, except names
+//! starting with `_`.
+//!
+//! A call receives its arguments as a JSON array written to memory returned by `alloc`, and
+//! returns the location of its JSON result packed as `ptr << 32 | len`. The result is either
+//! `{"ok": <value>}` or `{"error": "<message>"}`.
+//!
+//! Each call runs in a fresh instance limited by the plugin's [`Limits`]. Exports are bound to one of 256
+//! function slots shared by all plugins, which a plugin frees when it is dropped. A plugin replacing one with
+//! the same name takes over the slots of the exports they share, so contexts created before it call the new version.
+
+use crate::{
+    functions::{Module, Param, Signature},
+    macros::slots,
+    sensitive, Error,
+};
+
+use hcl::{
+    eval::{Context, Func, FuncArgs, FuncDef, ParamType},
+    expr::FuncName,
+};
+use serde::Deserialize;
+use std::{
+    cell::RefMut,
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+use wasmi::{core::ValType, Config, Engine, ExternType, Linker, Store, StoreLimits, StoreLimitsBuilder};
+
+const ROW: usize = 16;
+
+/// Exports bound to the function slots below, shared by all plugins.
+static EXPORTS: RwLock<Vec<Binding>> = RwLock::new(Vec::new());
+
+/// Identifies the plugin owning a slot.
+static IDS: AtomicU64 = AtomicU64::new(0);
+
+/// Evaluation functions are plain `fn` pointers, each export is bound to one of these.
+static SLOTS: [[Func; ROW]; ROW] = slots!(slot);
+
+/// Resources available to a single call.
+#[derive(Clone, Copy)]
+pub struct Limits {
+    /// Fuel consumed by executed instructions before the call is aborted.
+    pub fuel: u64,
+    /// Maximum size of the linear memory in bytes.
+    pub memory: usize,
+}
+
+/// A compiled WebAssembly plugin, registered with [`functions::register`](crate::functions::register).
+pub struct Plugin {
+    id: u64,
+    name: String,
+    source: String,
+    exports: Vec<(String, usize)>,
+}
+
+/// The export of a plugin bound to a function slot.
+struct Binding {
+    owner: u64,
+    namespace: String,
+    export: String,
+    /// `None` once the plugin owning the slot is dropped, until another export takes it.
+    runtime: Option<Arc<Runtime>>,
+}
+
+struct Runtime {
+    name: String,
+    engine: Engine,
+    module: wasmi::Module,
+    limits: Limits,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Output {
+    Ok(hcl::Value),
+    Error(String),
+}
+
+impl Default for Limits {
+    fn default() -> Self { Self { fuel: 10_000_000, memory: 16 << 20 } }
+}
+
+impl Plugin {
+    /// Compiles the module at `path` and binds its exports to functions in the `name` namespace.
+    pub fn load(name: impl Into<String>, path: impl AsRef<Path>, limits: Limits) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let source = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned();
+
+        Self::new(name, source, &fs::read(path)?, limits)
+    }
+
+    /// Compiles a module from its binary, `source` names it in descriptions.
+    pub fn new(name: impl Into<String>, source: impl Into<String>, wasm: &[u8], limits: Limits) -> Result<Self, Error> {
+        let name = name.into();
+        let failed = |err: wasmi::Error| Error::Plugin(format!("Cannot load plugin '{name}': {err}"));
+
+        let mut config = Config::default();
+        config.consume_fuel(true);
+
+        let engine = Engine::new(&config);
+        let module = wasmi::Module::new(&engine, wasm).map_err(failed)?;
+
+        if let Some(import) = module.imports().next() {
+            return Err(Error::Plugin(format!(
+                "Plugin '{name}' imports '{}::{}', plugins cannot import anything",
+                import.module(),
+                import.name()
+            )));
+        }
+
+        let callable = |ty: &ExternType| match ty {
+            ExternType::Func(func) => func.params() == [ValType::I32, ValType::I32] && func.results() == [ValType::I64],
+            _ => false,
+        };
+
+        let names: Vec<String> = module
+            .exports()
+            .filter(|export| callable(export.ty()) && export.name() != "alloc" && !export.name().starts_with('_'))
+            .map(|export| export.name().to_owned())
+            .collect();
+
+        if let Some(invalid) = names.iter().find(|export| hcl::Identifier::new(export.as_str()).is_err()) {
+            return Err(Error::Plugin(format!("Plugin '{name}' export '{invalid}' is not a valid function name")));
+        }
+
+        let runtime = Arc::new(Runtime {
+            name: name.clone(),
+            engine,
+            module,
+            limits,
+        });
+        let id = IDS.fetch_add(1, Ordering::Relaxed);
+        let mut bound = EXPORTS.write().unwrap();
+        let mut exports = Vec::new();
+
+        for export in names {
+            let binding = Binding {
+                owner: id,
+                namespace: name.clone(),
+                export: export.clone(),
+                runtime: Some(runtime.clone()),
+            };
+
+            let slot = match bound
+                .iter()
+                .position(|slot| slot.namespace == name && slot.export == export)
+                .or_else(|| bound.iter().position(|slot| slot.runtime.is_none()))
+            {
+                Some(slot) => slot,
+                None if bound.len() < ROW * ROW => {
+                    bound.push(binding);
+                    exports.push((export, bound.len() - 1));
+                    continue;
+                }
+                None => {
+                    release(&mut bound, id);
+                    return Err(Error::Plugin(format!("Cannot load plugin '{name}': at most {} plugin functions are supported", ROW * ROW)));
+                }
+            };
+
+            bound[slot] = binding;
+            exports.push((export, slot));
+        }
+
+        Ok(Self {
+            id,
+            name,
+            exports,
+            source: source.into(),
+        })
+    }
+
+    /// Names of the exported functions, without the namespace.
+    pub fn exports(&self) -> impl Iterator<Item = &str> { self.exports.iter().map(|(export, _)| export.as_str()) }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) { release(&mut EXPORTS.write().unwrap(), self.id) }
+}
+
+impl Module for Plugin {
+    fn name(&self) -> &str { &self.name }
+
+    fn declare(&self, mut ctx: RefMut<Context>) -> Vec<Signature> {
+        let description = format!("Exported by the WebAssembly plugin {}", self.source);
+
+        self.exports
+            .iter()
+            .map(|(export, slot)| {
+                let func_name = FuncName::new(export.as_str()).with_namespace(vec![self.name.as_str()]);
+                let func = FuncDef::builder().variadic_param(ParamType::Any).build(SLOTS[slot / ROW][slot % ROW]);
+
+                ctx.declare_func(func_name.clone(), func);
+                Signature::new(func_name, vec![Param::new("args", "Any", true)], &description, None)
+            })
+            .collect()
+    }
+}
+
+impl Runtime {
+    fn call(&self, export: &str, args: &[hcl::Value]) -> Result<hcl::Value, String> {
+        let failed = |err: wasmi::Error| format!("plugin '{}' failed: {err}", self.name);
+
+        let mut store = Store::new(&self.engine, StoreLimitsBuilder::new().memory_size(self.limits.memory).build());
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store.set_fuel(self.limits.fuel).map_err(|err| err.to_string())?;
+
+        let instance = Linker::new(&self.engine).instantiate(&mut store, &self.module).and_then(|pre| pre.start(&mut store)).map_err(failed)?;
+        let memory = instance.get_memory(&store, "memory").ok_or(format!("plugin '{}' does not export its memory", self.name))?;
+
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc").map_err(failed)?;
+        let func = instance.get_typed_func::<(i32, i32), i64>(&store, export).map_err(failed)?;
+
+        let input = serde_json::to_vec(args).map_err(|err| err.to_string())?;
+        let len = i32::try_from(input.len()).map_err(|_| "arguments are too large")?;
+        let ptr = alloc.call(&mut store, len).map_err(failed)?;
+
+        memory.write(&mut store, ptr as u32 as usize, &input).map_err(|err| format!("plugin '{}' failed: {err}", self.name))?;
+
+        let packed = func.call(&mut store, (ptr, len)).map_err(failed)? as u64;
+        let (ptr, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+
+        let output = memory
+            .data(&store)
+            .get(ptr..ptr + len)
+            .ok_or(format!("plugin '{}' returned a result outside of its memory", self.name))?;
+
+        match serde_json::from_slice(output) {
+            Ok(Output::Ok(value)) => Ok(value),
+            Ok(Output::Error(message)) => Err(message),
+            Err(err) => Err(format!("plugin '{}' returned an invalid result: {err}", self.name)),
+        }
+    }
+}
+
+/// Frees the slots still owned by the plugin `owner`.
+fn release(bound: &mut [Binding], owner: u64) { bound.iter_mut().filter(|slot| slot.owner == owner).for_each(|slot| slot.runtime = None) }
+
+fn slot<const N: usize>(args: FuncArgs) -> Result<hcl::Value, String> {
+    sensitive::call(
+        |args| {
+            let (runtime, export) = {
+                let bound = EXPORTS.read().unwrap();
+                let slot = &bound[N];
+
+                match &slot.runtime {
+                    Some(runtime) => (runtime.clone(), slot.export.clone()),
+                    None => return Err(format!("plugin '{}' was unloaded", slot.namespace)),
+                }
+            };
+
+            runtime.call(&export, &args)
+        },
+        args,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{functions, HclConverter};
+
+    /// A plugin whose `value` export returns `{"ok":<value>}`.
+    fn plugin(name: &str, value: u8) -> Plugin {
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{{\"ok\":{value}}}")
+                (func (export "alloc") (param i32) (result i32) i32.const 1024)
+                (func (export "value") (param i32 i32) (result i64) i64.const 8))"#
+        );
+
+        Plugin::new(name, "test.wasm", &wat::parse_str(wat).unwrap(), Limits::default()).unwrap()
+    }
+
+    fn value(name: &str) -> hcl::Value {
+        let hcl = HclConverter::builder().source(format!("meta {{}}\n\nvalue = {name}::value()\n")).build().unwrap();
+        hcl.value().unwrap().as_object().unwrap()["value"].to_owned()
+    }
+
+    #[test]
+    fn replaced_plugins_are_called() {
+        functions::register(plugin("replaced", 1));
+        assert_eq!(value("replaced"), hcl::Value::from(1));
+
+        let before = HclConverter::builder().source("meta {}\n\nvalue = replaced::value()\n").build().unwrap();
+        functions::register(plugin("replaced", 2));
+
+        assert_eq!(value("replaced"), hcl::Value::from(2));
+        assert_eq!(before.value().unwrap().as_object().unwrap()["value"], hcl::Value::from(2));
+    }
+
+    #[test]
+    fn reloading_frees_slots() {
+        for value in 0..ROW * ROW + 16 {
+            functions::register(plugin("reloaded", (value % 10) as u8));
+        }
+
+        assert_eq!(value("reloaded"), hcl::Value::from((ROW * ROW + 15) % 10));
+
+        // dropped plugins free their slots for the exports of others
+        for index in 0..ROW * ROW + 16 {
+            drop(plugin(&format!("dropped{index}"), 0));
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Validation of rendered documents against a JSON Schema, set with `meta.schema`.
+//!
+//! Schemas are compiled with the `jsonschema` crate, which picks the draft from `$schema` and defaults
+//! to 2020-12. `$ref` resolves within the schema only, `format` is treated as an annotation, and
+//! `pattern` is matched in linear time, so a schema cannot stall a render with a pathological regex.
+
+use jsonschema::{PatternOptions, Validator};
+use serde_json::Value;
+use std::fmt;
+
+/// A compiled JSON Schema.
+#[derive(Clone, Debug)]
+pub struct Schema {
+    validator: Validator,
+}
+
+/// A part of the document that does not match the schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    /// JSON pointer to the offending value, empty for the document itself.
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pointer.as_str() {
+            "" => write!(f, "(root): {}", self.message),
+            pointer => write!(f, "{pointer}: {}", self.message),
+        }
+    }
+}
+
+impl Schema {
+    /// Compiles a schema, failing with a description of the problem if it is not a valid JSON Schema.
+    pub fn new(root: &Value) -> Result<Self, String> {
+        let validator = jsonschema::options()
+            .should_validate_formats(false)
+            .with_pattern_options(PatternOptions::regex())
+            .build(root)
+            .map_err(|err| format!("is not a valid JSON Schema: {err}"))?;
+
+        Ok(Schema { validator })
+    }
+
+    /// Parses and compiles a schema from JSON text.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let root: Value = serde_json::from_str(text).map_err(|err| format!("is not valid JSON: {err}"))?;
+        Schema::new(&root)
+    }
+
+    /// Every violation of the schema by `value`.
+    pub fn validate(&self, value: &Value) -> Vec<Violation> {
+        self.validator
+            .iter_errors(value)
+            .map(|err| Violation {
+                pointer: err.instance_path().as_str().to_owned(),
+                message: err.to_string(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    sync::{Arc, OnceLock},
+};
+
+/// A source of secrets for the `secret::kv` function.
+pub trait SecretProvider: Send + Sync {
+    /// Reads the secret stored at `path` and returns its key/value pairs.
+    fn kv(&self, path: &str) -> Result<hcl::Value, String>;
+}
+
+/// Reads secrets from the KV v2 engine of a HashiCorp Vault server.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Vault {
+    pub url: String,
+    pub token: String,
+}
+
+static DEFAULT: OnceLock<Arc<dyn SecretProvider>> = OnceLock::new();
+
+thread_local! {
+    static ACTIVE: RefCell<Option<Arc<dyn SecretProvider>>> = const { RefCell::new(None) };
+}
+
+/// Sets the provider used by documents that were not given one explicitly.
+///
+/// Only the first call has an effect.
+pub fn set_default(provider: impl SecretProvider + 'static) { DEFAULT.get_or_init(|| Arc::new(provider)); }
+
+pub(crate) fn current() -> Option<Arc<dyn SecretProvider>> { ACTIVE.with_borrow(Clone::clone).or_else(|| DEFAULT.get().cloned()) }
+
+pub(crate) fn scoped<T>(provider: Option<&Arc<dyn SecretProvider>>, f: impl FnOnce() -> T) -> T {
+    let previous = ACTIVE.replace(provider.cloned());
+    let result = f();
+
+    ACTIVE.set(previous);
+    result
+}
+
+impl Vault {
+    pub fn new(url: impl Into<String>, token: impl Into<String>) -> Self { Self { url: url.into(), token: token.into() } }
+}
+
+impl SecretProvider for Vault {
+    fn kv(&self, path: &str) -> Result<hcl::Value, String> {
+        let client = reqwest::blocking::Client::new();
+        let request = client.get(format!("{}/v1/kv/data/{path}", self.url)).header("X-Vault-Token", &self.token);
+
+        let response = request.send().map_err(|e| format!("HTTP GET request failed: {}", e))?;
+        let json = response.json::<hcl::Map<String, hcl::Value>>().map_err(|e| format!("Failed to read response: {}", e))?;
+        let data = json.get("data").ok_or("Unable to decode json")?;
+
+        match data.as_object().and_then(|values| values.get("data")) {
+            Some(secret) if secret.is_object() => Ok(secret.to_owned()),
+            _ => Ok(data.to_owned()),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! Tracking of sensitive values, such as secrets, so they can be kept out of errors and output.
+//!
+//! Values passed to `sensitive()` or returned by `secret::kv` are marked while a document is
+//! rendered. Functions called with a marked value mark their result too, and every string
+//! containing a marked value is redacted from error messages. Whether marked values may appear
+//! in the rendered output is decided by a [`Policy`].
+//!
+//! Marks are the strings themselves rather than the values they came from, so only strings can be
+//! marked. Numbers and bools are left alone, as marking `5` would mark every `5` in the document.
+//! For the same reason, marks shorter than [`MIN_EMBEDDED`] characters only match whole strings and
+//! are not looked for inside longer strings or messages.
+
+use crate::Error;
+
+use hcl::eval::{Func, FuncArgs};
+use std::{cell::RefCell, collections::HashSet, rc::Rc, str::FromStr};
+
+/// Replaces sensitive values in errors and redacted output.
+pub const REDACTED: &str = "(sensitive)";
+
+/// Length a mark needs to be recognized inside a longer string, such as an interpolated template.
+pub const MIN_EMBEDDED: usize = 6;
+
+/// Whether sensitive values may appear in the rendered output, ordered from least to most strict.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Policy {
+    /// Sensitive values are rendered as they are.
+    #[default]
+    Allow,
+    /// Strings containing sensitive values are rendered as [`REDACTED`].
+    Redact,
+    /// Rendering fails if the output contains a sensitive value.
+    Deny,
+}
+
+impl FromStr for Policy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Policy::Allow),
+            "redact" => Ok(Policy::Redact),
+            "deny" => Ok(Policy::Deny),
+            _ => Err(Error::UnknownPolicy(s.to_owned())),
+        }
+    }
+}
+
+/// The strings marked sensitive while rendering a document and the files it loads.
+#[derive(Clone, Default)]
+pub(crate) struct Marks(Rc<RefCell<HashSet<String>>>);
+
+thread_local! {
+    static ACTIVE: RefCell<Option<Marks>> = const { RefCell::new(None) };
+}
+
+impl Marks {
+    /// Makes these marks the ones `sensitive()` and function calls record into while `f` runs.
+    pub(crate) fn scoped<T>(&self, f: impl FnOnce() -> T) -> T {
+        let previous = ACTIVE.replace(Some(self.clone()));
+        let result = f();
+
+        ACTIVE.set(previous);
+        result
+    }
+
+    fn mark(&self, value: &hcl::Value) {
+        let mut marks = self.0.borrow_mut();
+        strings(value, &mut |text| {
+            if !text.is_empty() {
+                marks.insert(text.to_owned());
+            }
+        });
+    }
+
+    /// Whether any string in `value` is or embeds a sensitive value.
+    fn contains(&self, value: &hcl::Value) -> bool {
+        let marks = self.0.borrow();
+        let mut found = false;
+
+        strings(value, &mut |text| {
+            found = found || marks.contains(text) || marks.iter().any(|mark| embedded(mark) && text.contains(mark.as_str()))
+        });
+        found
+    }
+
+    /// Replaces every sensitive value in `text`, longest first so overlapping values leave nothing behind.
+    ///
+    /// Marks shorter than [`MIN_EMBEDDED`] are only replaced when they are all of `text`.
+    pub(crate) fn redact(&self, text: &str) -> String {
+        let marks = self.0.borrow();
+
+        if marks.contains(text) {
+            return REDACTED.to_owned();
+        }
+
+        let mut marks: Vec<&String> = marks.iter().filter(|mark| embedded(mark) && text.contains(mark.as_str())).collect();
+        marks.sort_by_key(|mark| std::cmp::Reverse(mark.len()));
+
+        marks.into_iter().fold(text.to_owned(), |text, mark| text.replace(mark.as_str(), REDACTED))
+    }
+
+    /// The error with sensitive values removed from its message, keeping its status.
+    pub(crate) fn redact_error(&self, err: Error) -> Error {
+        let message = err.to_string();
+        let redacted = self.redact(&message);
+
+        match redacted == message {
+            true => err,
+            false => Error::Redacted {
+                status: err.status(),
+                message: redacted,
+            },
+        }
+    }
+
+    /// Applies `policy` to a rendered value.
+    pub(crate) fn apply(&self, policy: Policy, value: hcl::Value) -> Result<hcl::Value, Error> {
+        match policy {
+            Policy::Allow => Ok(value),
+            Policy::Redact => Ok(self.redact_value(value)),
+            Policy::Deny => {
+                let mut pointers = Vec::new();
+                self.find(&value, &mut String::new(), &mut pointers);
+
+                match pointers.is_empty() {
+                    true => Ok(value),
+                    false => Err(Error::SensitiveOutput(pointers)),
+                }
+            }
+        }
+    }
+
+    fn redact_value(&self, value: hcl::Value) -> hcl::Value {
+        match value {
+            hcl::Value::String(text) if self.contains(&hcl::Value::String(text.to_owned())) => hcl::Value::from(REDACTED),
+            hcl::Value::Array(items) => hcl::Value::Array(items.into_iter().map(|item| self.redact_value(item)).collect()),
+            hcl::Value::Object(obj) => hcl::Value::Object(obj.into_iter().map(|(key, item)| (self.redact(&key), self.redact_value(item))).collect()),
+            value => value,
+        }
+    }
+
+    /// Collects the JSON pointers of the strings in `value` containing sensitive values.
+    fn find(&self, value: &hcl::Value, pointer: &mut String, out: &mut Vec<String>) {
+        match value {
+            hcl::Value::String(_) if self.contains(value) => out.push(pointer.to_owned()),
+            hcl::Value::Array(items) => items.iter().enumerate().for_each(|(index, item)| self.find_at(&index.to_string(), item, pointer, out)),
+            hcl::Value::Object(obj) => obj.iter().for_each(|(key, item)| self.find_at(key, item, pointer, out)),
+            _ => {}
+        }
+    }
+
+    fn find_at(&self, segment: &str, value: &hcl::Value, pointer: &mut String, out: &mut Vec<String>) {
+        let len = pointer.len();
+
+        pointer.push('/');
+        pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+        self.find(value, pointer, out);
+        pointer.truncate(len);
+    }
+}
+
+/// Marks the strings in `value` as sensitive in the document being rendered.
+pub(crate) fn mark(value: &hcl::Value) { ACTIVE.with_borrow(|marks| marks.iter().for_each(|marks| marks.mark(value))) }
+
+/// Calls a function, marking its result when an argument is sensitive and redacting its error.
+#[doc(hidden)]
+pub fn call(func: Func, args: FuncArgs) -> Result<hcl::Value, String> {
+    let Some(marks) = ACTIVE.with_borrow(Clone::clone) else { return func(args) };
+    let tainted = args.iter().any(|arg| marks.contains(arg));
+
+    match func(args) {
+        Ok(value) if tainted => {
+            marks.mark(&value);
+            Ok(value)
+        }
+        Ok(value) => Ok(value),
+        Err(err) => Err(marks.redact(&err)),
+    }
+}
+
+fn embedded(mark: &str) -> bool { mark.chars().count() >= MIN_EMBEDDED }
+
+fn strings(value: &hcl::Value, f: &mut impl FnMut(&str)) {
+    match value {
+        hcl::Value::String(text) => f(text),
+        hcl::Value::Array(items) => items.iter().for_each(|item| strings(item, f)),
+        hcl::Value::Object(obj) => obj.values().for_each(|item| strings(item, f)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HclConverter, Language};
+
+    fn marks(values: &[&str]) -> Marks {
+        let marks = Marks::default();
+        values.iter().for_each(|value| marks.mark(&hcl::Value::from(*value)));
+        marks
+    }
+
+    #[test]
+    fn short_marks_only_match_whole_strings() {
+        let marks = marks(&["a"]);
+
+        assert!(marks.contains(&hcl::Value::from("a")));
+        assert!(!marks.contains(&hcl::Value::from("platform")));
+        assert_eq!(marks.redact("a"), REDACTED);
+    }
+
+    #[test]
+    fn long_marks_match_inside_strings() {
+        let marks = marks(&["hunter2"]);
+
+        assert!(marks.contains(&hcl::Value::from("postgres://admin:hunter2@db")));
+        assert_eq!(marks.redact("wrong password 'hunter2'"), "wrong password '(sensitive)'");
+    }
+
+    #[test]
+    fn messages_stay_readable() {
+        let marks = marks(&["a", "n", "/"]);
+        let err = marks.redact_error(Error::SensitiveOutput(vec!["/name".into()]));
+
+        assert!(!matches!(err, Error::Redacted { .. }));
+        assert!(!err.to_string().contains(REDACTED));
+    }
+
+    #[test]
+    fn deny_passes_unrelated_strings() {
+        let hcl = HclConverter::builder()
+            .source("meta {\n  sensitive = \"deny\"\n}\n\nlocals {\n  pw = sensitive(\"a\")\n}\n\nname = \"platform\"\n")
+            .build()
+            .unwrap();
+
+        assert_eq!(hcl.render(&Language::JSON).unwrap().trim(), "{\n  \"name\": \"platform\"\n}");
+    }
+
+    #[test]
+    fn deny_catches_embedded_secrets() {
+        let hcl = HclConverter::builder()
+            .source("meta {\n  sensitive = \"deny\"\n}\n\nlocals {\n  pw = sensitive(\"hunter2\")\n}\n\nname = \"platform\"\nurl  = \"postgres://admin:${local.pw}@db\"\n")
+            .build()
+            .unwrap();
+
+        match hcl.render(&Language::JSON) {
+            Err(Error::SensitiveOutput(pointers)) => assert_eq!(pointers, ["/url"]),
+            other => panic!("expected the url to be denied, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn redact_keeps_unrelated_strings() {
+        let hcl = HclConverter::builder()
+            .source("meta {\n  sensitive = \"redact\"\n}\n\nlocals {\n  user = sensitive(\"app\")\n}\n\nname = \"application\"\nuser = local.user\n")
+            .build()
+            .unwrap();
+
+        let value = hcl.output().unwrap();
+        let value = value.as_object().unwrap();
+        assert_eq!(value["name"], hcl::Value::from("application"));
+        assert_eq!(value["user"], hcl::Value::from(REDACTED));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::models;
+use ship::{functions, Artifact, HclConverter, Language};
+
+use serde::Deserialize;
+use std::{io, path::Path, time::SystemTime};
+use tide::{utils::After, Body, Request, Response};
+use tide_tracing::TraceMiddleware;
+
+#[derive(Deserialize)]
+struct Params {
+    lang: Option<String>,
+    sensitive: Option<String>,
+    nulls: Option<String>,
+    archive: Option<String>,
+}
+
+fn error(err: ship::Error) -> tide::Error {
+    let status = err.status();
+    tide::Error::new(status, err)
+}
+
+async fn compile(req: Request<models::Config>) -> tide::Result {
+    let mut res = Response::new(200);
+
+    let params: Params = req.query()?;
+    let base = &req.state().settings.storage;
+    let path = req.param("path").unwrap_or_default();
+
+    // `/<path>/<output>` names an output block of the file at `<path>`, unless the full path is a file itself
+    let (file, output) = match path.rsplit_once('/') {
+        Some((file, output)) if !is_document(&Path::new(base).join(path)) && is_document(&Path::new(base).join(file)) => (file, Some(output)),
+        _ => (path, None),
+    };
+
+    let mut builder = HclConverter::builder().storage(base).file(file);
+
+    if let Some(policy) = &params.sensitive {
+        builder = builder.sensitive(policy.parse().map_err(error)?);
+    }
+
+    if let Some(policy) = &params.nulls {
+        builder = builder.nulls(policy.parse().map_err(error)?);
+    }
+
+    for (key, value) in req.url().query_pairs() {
+        if let Some(name) = key.strip_prefix("var.") {
+            builder = builder.variable(name, value.into_owned());
+        }
+    }
+
+    let hcl = builder.build().map_err(error)?;
+
+    if let Some(name) = output {
+        let artifact = hcl.artifact(name).map_err(error)?;
+        let file = Path::new(&artifact.file).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+
+        res.set_body(artifact.data);
+        res.insert_header("Content-Disposition", format!(r#"attachment; filename="{file}""#));
+
+        return Ok(res);
+    }
+
+    let lang = match &params.lang {
+        Some(lang) => Language::parse(lang),
+        None => hcl.language(),
+    };
+    let file = hcl.file_name().unwrap_or(file.rsplit_once('.').map(|(name, _)| name).unwrap_or(file)).to_owned();
+
+    if let Some(format) = &params.archive {
+        if format != "tar" {
+            return Err(tide::Error::from_str(400, format!("Unknown archive format '{format}', expected tar")));
+        }
+
+        res.set_body(archive(&hcl.artifacts().map_err(error)?)?);
+        res.insert_header("Content-Type", "application/x-tar");
+        res.insert_header("Content-Disposition", format!(r#"attachment; filename="{file}.tar""#));
+
+        return Ok(res);
+    }
+
+    let data = hcl.render(&lang).map_err(error)?;
+    let ext = hcl.extension(&lang);
+
+    res.set_body(data);
+    res.insert_header("Content-Disposition", format!(r#"attachment; filename="{file}.{ext}""#));
+
+    Ok(res)
+}
+
+/// Whether `path` is a file, or a directory with an `index.hcl`, that the builder can load.
+fn is_document(path: &Path) -> bool {
+    match path.is_dir() {
+        true => path.join("index.hcl").is_file(),
+        false => path.is_file() && path.extension().is_some_and(|ext| ext == "hcl"),
+    }
+}
+
+/// A tar archive with every artifact at its file name.
+fn archive(artifacts: &[Artifact]) -> io::Result<Vec<u8>> {
+    let mtime = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or_default();
+    let mut builder = tar::Builder::new(Vec::new());
+
+    for artifact in artifacts {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(artifact.data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        builder.append_data(&mut header, &artifact.file, artifact.data.as_bytes())?;
+    }
+
+    builder.into_inner()
+}
+
+async fn catalog(_req: Request<models::Config>) -> tide::Result {
+    let mut res = Response::new(200);
+    res.set_body(Body::from_json(&functions::catalog())?);
+    Ok(res)
+}
+
+pub async fn start(config: models::Config) -> tide::Result<()> {
+    let sub = tracing_subscriber::fmt().json();
+    let mut app = tide::with_state(config.to_owned());
+
+    let level = config.settings.log.parse().unwrap_or(tracing::Level::INFO);
+
+    sub.with_max_level(level).init();
+    app.with(TraceMiddleware::new());
+
+    app.with(After(|mut res: Response| async {
+        if let Some(error) = res.take_error() {
+            let status = error.status();
+
+            res.set_status(status);
+            res.set_body(format!("(message)\n{error}\n\n(error)\n{status}\n"));
+        }
+        Ok(res)
+    }));
+
+    app.at("/_functions").get(catalog);
+    app.at("/*path").get(compile);
+    app.listen(config.settings.listen).await?;
+
+    Ok(())
+}